@@ -0,0 +1,218 @@
+/* Generated by cbindgen from v2/ffi.rs, do not edit */
+
+#ifndef BREAKTHROUGH_H
+#define BREAKTHROUGH_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/* The longest principal variation a search result holds */
+#define BT_MAX_PV 64
+
+/* The most legal moves any position can have, including ones loaded from FEN */
+#define BT_MAX_MOVES 192
+
+typedef enum BtPlayer {
+  BT_WHITE,
+  BT_BLACK,
+} BtPlayer;
+
+typedef enum BtResult {
+  BT_UNDECIDED,
+  BT_WHITE_WINS,
+  BT_BLACK_WINS,
+} BtResult;
+
+typedef enum BtEvalKind {
+  /**
+   * `value` is a score, positive when White is better
+   */
+  BT_HEURISTIC,
+  /**
+   * `value` is the ply of the game at which White wins
+   */
+  BT_WHITE_WIN_PLY,
+  /**
+   * `value` is the ply of the game at which Black wins
+   */
+  BT_BLACK_WIN_PLY,
+} BtEvalKind;
+
+/**
+ * A position, from `bt_position_new` or `bt_position_from_fen`
+ */
+typedef struct BtPosition BtPosition;
+
+/**
+ * An engine with its own transposition table, from `bt_searcher_new`
+ */
+typedef struct BtSearcher BtSearcher;
+
+/**
+ * Squares are numbered `row * 8 + col`, where row 0 is rank 8 and col 0 is file a
+ */
+typedef struct BtMove {
+  uint8_t from;
+  uint8_t to;
+} BtMove;
+
+/**
+ * Zero means no limit for either field. The time limit only applies once depth 1
+ * is done, so a search always finds a move.
+ */
+typedef struct BtLimits {
+  uint32_t depth;
+  uint64_t time_ms;
+} BtLimits;
+
+/**
+ * An evaluation from White's point of view
+ */
+typedef struct BtEval {
+  enum BtEvalKind kind;
+  int64_t value;
+} BtEval;
+
+typedef struct BtSearchResult {
+  struct BtMove best;
+  struct BtEval eval;
+  /**
+   * The deepest depth that finished
+   */
+  uint32_t depth;
+  uint64_t elapsed_ms;
+  /**
+   * The first `pv_len` moves of `pv` are the expected line, starting with `best`
+   */
+  uint32_t pv_len;
+  struct BtMove pv[BT_MAX_PV];
+} BtSearchResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The starting position
+ */
+struct BtPosition *bt_position_new(void);
+
+/**
+ * A position from FEN, e.g. "bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1",
+ * or NULL if it's invalid.
+ *
+ * # Safety
+ * `fen` must be NULL or a NUL-terminated string.
+ */
+struct BtPosition *bt_position_from_fen(const char *fen);
+
+/**
+ * # Safety
+ * `position` must be a live position handle.
+ */
+struct BtPosition *bt_position_clone(const struct BtPosition *position);
+
+/**
+ * Writes the FEN into `buf`, truncated to `len` bytes including the NUL, and
+ * returns its full length.
+ *
+ * # Safety
+ * `position` must be a live position handle and `buf` must be NULL or hold `len` bytes.
+ */
+size_t bt_position_fen(const struct BtPosition *position, char *buf, size_t len);
+
+/**
+ * # Safety
+ * `position` must be a live position handle.
+ */
+enum BtPlayer bt_position_to_play(const struct BtPosition *position);
+
+/**
+ * # Safety
+ * `position` must be a live position handle.
+ */
+enum BtResult bt_position_result(const struct BtPosition *position);
+
+/**
+ * Writes up to `capacity` legal moves into `moves` and returns how many there are
+ * in all, which is never more than `BT_MAX_MOVES`. A buffer of that size always
+ * holds them; otherwise call with a NULL buffer first to get the count. There are
+ * none once the game is over.
+ *
+ * # Safety
+ * `position` must be a live position handle and `moves` must be NULL or hold
+ * `capacity` moves.
+ */
+size_t bt_position_legal_moves(const struct BtPosition *position,
+                               struct BtMove *moves,
+                               size_t capacity);
+
+/**
+ * Plays a move, returning false and leaving the position alone if it's illegal.
+ *
+ * # Safety
+ * `position` must be a live position handle.
+ */
+bool bt_position_make_move(struct BtPosition *position, struct BtMove action);
+
+/**
+ * # Safety
+ * `position` must be NULL or a position handle that hasn't been freed yet.
+ */
+void bt_position_free(struct BtPosition *position);
+
+/**
+ * Parses a move like "d2d3", returning false if it isn't one. Legality isn't checked.
+ *
+ * # Safety
+ * `s` must be NULL or a NUL-terminated string, and `action` a valid pointer.
+ */
+bool bt_move_parse(const char *s, struct BtMove *action);
+
+/**
+ * Writes a move like "d2d3" into `buf`, which needs 5 bytes for the whole move.
+ * Returns the length of the move text, or 0 with an empty string if a square is
+ * off the board.
+ *
+ * # Safety
+ * `buf` must be NULL or hold `len` bytes.
+ */
+size_t bt_move_to_string(struct BtMove action, char *buf, size_t len);
+
+/**
+ * A searcher with a table of `table_size` entries, using the evaluation parameters
+ * in the file at `params_path`, or the defaults if it's NULL. Returns NULL if the
+ * file can't be read.
+ *
+ * # Safety
+ * `params_path` must be NULL or a NUL-terminated string.
+ */
+struct BtSearcher *bt_searcher_new(size_t table_size, const char *params_path);
+
+/**
+ * Searches `position` within `limits` and fills in `result`. Returns false, leaving
+ * `result` alone, if the game is already over or the side to play has no moves.
+ *
+ * # Safety
+ * `searcher` and `position` must be live handles and `result` a valid pointer. A
+ * searcher must not be used by two threads at once.
+ */
+bool bt_searcher_search(struct BtSearcher *searcher,
+                        const struct BtPosition *position,
+                        struct BtLimits limits,
+                        struct BtSearchResult *result);
+
+/**
+ * # Safety
+ * `searcher` must be NULL or a searcher handle that hasn't been freed yet.
+ */
+void bt_searcher_free(struct BtSearcher *searcher);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BREAKTHROUGH_H */
@@ -1,7 +1,19 @@
-use std::{io, str::FromStr};
+#[cfg(feature = "serve")]
+mod serve;
+#[cfg(feature = "tui")]
+mod tui;
+
+use std::{fs::{self, File}, io::{self, BufWriter}, path::{Path, PathBuf}, str::FromStr};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
 
 use clap::{Parser, ValueEnum};
-use v2::{core::{node8::BreakthroughNode, move8::BreakthroughMove, Evaluation, Player}, search::{evaluate_with_ttable, table::TranspositionTable}};
+#[cfg(feature = "serve")]
+use serve::ServeOptions;
+#[cfg(feature = "tui")]
+use tui::TuiOptions;
+use v2::{book::OpeningBook, core::{node8::BreakthroughNode, move8::BreakthroughMove, Evaluation, Player}, evaluation::{explain::explain, network::{Network, NetworkEvaluator}, params::EvalParams, Evaluator}, game::Game, record::{self, sgf, GameRecord}, render::{svg::{svg_diagram, DiagramOptions}, BoardRenderer, RenderStyle}, search::{evaluate_with_ttable, table::TranspositionTable}, tuning::{self, datagen::{self, random_opening, DatagenConfig}}};
 
 #[derive(Parser, Debug)]
 struct Cli {
@@ -16,13 +28,234 @@ enum Commands {
         strategy: PlayStrategy,
         #[arg(long, default_value = "8")]
         depth: u32,
+        /// Evaluation parameter file used by White
+        #[arg(long)]
+        white_params: Option<PathBuf>,
+        /// Evaluation parameter file used by Black
+        #[arg(long)]
+        black_params: Option<PathBuf>,
+        /// Network file used by White instead of the parameters
+        #[arg(long)]
+        white_network: Option<PathBuf>,
+        /// Network file used by Black instead of the parameters
+        #[arg(long)]
+        black_network: Option<PathBuf>,
+        /// How boards are drawn
+        #[arg(long, default_value = "ascii")]
+        render: RenderMode,
+        /// Append the finished game to this record file
+        #[arg(long)]
+        record: Option<PathBuf>,
+        /// Opening book consulted before searching
+        #[arg(long)]
+        book: Option<PathBuf>,
+        /// Pick book moves at random by weight instead of the heaviest one
+        #[arg(long)]
+        book_random: bool,
+        /// Seed for random book moves, random if not given
+        #[arg(long)]
+        seed: Option<u64>,
     },
     Play {
         #[arg(long, default_value = "random")]
         strategy: PlayStrategy,
         #[arg(long, default_value = "8")]
         depth: u32,
-    }
+        /// Evaluation parameter file used by the engine
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Network file used by the engine instead of the parameters
+        #[arg(long)]
+        network: Option<PathBuf>,
+        /// Side played by the human, the engine plays the others
+        #[arg(long, default_value = "white")]
+        human_color: HumanColor,
+        /// Position to start from instead of the usual one
+        #[arg(long)]
+        fen: Option<String>,
+        /// Moves already played from the start, e.g. "d2d3 c7c6"
+        #[arg(long)]
+        moves: Option<String>,
+        /// How boards are drawn
+        #[arg(long, default_value = "ascii")]
+        render: RenderMode,
+        /// Append the finished game to this record file
+        #[arg(long)]
+        record: Option<PathBuf>,
+        /// Opening book consulted before searching
+        #[arg(long)]
+        book: Option<PathBuf>,
+        /// Pick book moves at random by weight instead of the heaviest one
+        #[arg(long)]
+        book_random: bool,
+        /// Seed for random book moves, random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Play or analyse in a full-screen terminal UI
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Deepest search for an engine move
+        #[arg(long, default_value = "8")]
+        depth: u32,
+        /// Time limit for an engine move, in milliseconds
+        #[arg(long)]
+        time_ms: Option<u64>,
+        /// Evaluation parameter file used by the engine
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Network file used by the engine instead of the parameters
+        #[arg(long)]
+        network: Option<PathBuf>,
+        /// Side played by the human, the engine plays the others
+        #[arg(long, default_value = "white")]
+        human_color: HumanColor,
+        /// Position to start from instead of the usual one
+        #[arg(long)]
+        fen: Option<String>,
+        /// Moves already played from the start, e.g. "d2d3 c7c6"
+        #[arg(long)]
+        moves: Option<String>,
+        /// Append the game to this record file on quitting
+        #[arg(long)]
+        record: Option<PathBuf>,
+    },
+    /// Run a local HTTP server with a JSON API for front-ends
+    #[cfg(feature = "serve")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1")]
+        address: String,
+        #[arg(long, default_value = "8080")]
+        port: u16,
+        /// Requests handled at the same time
+        #[arg(long, default_value = "4")]
+        threads: usize,
+        /// Search depth when a request doesn't give one
+        #[arg(long, default_value = "8")]
+        depth: u32,
+        /// Deepest search a request can ask for
+        #[arg(long, default_value = "12")]
+        max_depth: u32,
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Network file used instead of the parameters
+        #[arg(long)]
+        network: Option<PathBuf>,
+    },
+    /// Play two parameter sets against each other from random openings
+    Match {
+        /// Parameter file for the first engine
+        #[arg(long)]
+        a: Option<PathBuf>,
+        /// Parameter file for the second engine
+        #[arg(long)]
+        b: Option<PathBuf>,
+        /// Network file for the first engine instead of parameters
+        #[arg(long)]
+        a_network: Option<PathBuf>,
+        /// Network file for the second engine instead of parameters
+        #[arg(long)]
+        b_network: Option<PathBuf>,
+        /// Number of openings, each is played once with either color
+        #[arg(long, default_value = "20")]
+        openings: u32,
+        #[arg(long, default_value = "4")]
+        depth: u32,
+        /// Random moves played before the engines take over
+        #[arg(long, default_value = "4")]
+        random_plies: u32,
+        #[arg(long, default_value = "0")]
+        seed: u64,
+    },
+    /// Break the evaluation of a position down by term and square
+    Explain {
+        /// Position to explain, defaults to the starting position
+        #[arg(long)]
+        fen: Option<String>,
+        #[arg(long)]
+        params: Option<PathBuf>,
+    },
+    /// Draw a position as an SVG diagram
+    Diagram {
+        /// Position to draw, defaults to the starting position
+        #[arg(long)]
+        fen: Option<String>,
+        /// Moves to draw as arrows, e.g. a move or a line "d2d3 c7c6"
+        #[arg(long)]
+        arrows: Option<String>,
+        /// Squares to highlight, e.g. "d3 e4"
+        #[arg(long)]
+        highlight: Option<String>,
+        /// Draw the board from Black's side
+        #[arg(long)]
+        flip: bool,
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Build an opening book from game records
+    Book {
+        /// Record files to read, `.sgf` files are imported as SGF
+        #[arg(required = true)]
+        records: Vec<PathBuf>,
+        #[arg(long)]
+        output: PathBuf,
+        /// Only moves from this many plies into each game are kept
+        #[arg(long, default_value = "16")]
+        max_plies: usize,
+        /// Drop moves with a smaller total weight
+        #[arg(long, default_value = "2")]
+        min_weight: u32,
+    },
+    /// Convert SGF games, e.g. from Little Golem, into a record file
+    Import {
+        sgf: PathBuf,
+        /// Record file the games are appended to
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Generate training positions from self-play games
+    Datagen {
+        /// File the positions are written to, one `<fen> <score> <winner>` per line
+        #[arg(long)]
+        output: PathBuf,
+        #[arg(long, default_value = "100")]
+        games: u64,
+        #[arg(long, default_value = "4")]
+        depth: u32,
+        /// Random moves played at the start of each game
+        #[arg(long, default_value = "8")]
+        random_plies: u32,
+        #[arg(long, default_value = "1")]
+        threads: usize,
+        #[arg(long, default_value = "0")]
+        seed: u64,
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Network file used instead of the parameters
+        #[arg(long)]
+        network: Option<PathBuf>,
+    },
+    /// Tune evaluation parameters against game outcomes
+    Tune {
+        /// Positions file with one `<fen> [score] <winner>` per line
+        data: PathBuf,
+        /// Parameter file to start from instead of the defaults
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Where to write the tuned parameters
+        #[arg(long)]
+        output: PathBuf,
+        #[arg(long, default_value = "100")]
+        passes: usize,
+    },
+    /// Print evaluation parameters, optionally writing them to a file
+    Params {
+        /// Parameter file to start from instead of the defaults
+        #[arg(long)]
+        params: Option<PathBuf>,
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -31,92 +264,485 @@ enum PlayStrategy {
     V2,
 }
 
-fn evaluate_iterative_deepening(node: &BreakthroughNode, depth: u32, table: &mut TranspositionTable) -> (BreakthroughMove, Evaluation) {
+#[derive(Clone, Debug, PartialEq, ValueEnum)]
+enum HumanColor {
+    White,
+    Black,
+    /// Two people taking turns at the same keyboard
+    Both,
+    /// The engine plays itself
+    None,
+}
+
+#[derive(Clone, Debug, ValueEnum)]
+enum RenderMode {
+    Ascii,
+    Unicode,
+    /// Checkered board drawn with ANSI colors
+    Color,
+}
+
+impl RenderMode {
+    fn renderer(&self) -> BoardRenderer {
+        BoardRenderer::new(match self {
+            RenderMode::Ascii => RenderStyle::Ascii,
+            RenderMode::Unicode => RenderStyle::Unicode,
+            RenderMode::Color => RenderStyle::Color,
+        })
+    }
+}
+
+impl HumanColor {
+    fn plays(&self, player: &Player) -> bool {
+        match self {
+            HumanColor::White => *player == Player::White,
+            HumanColor::Black => *player == Player::Black,
+            HumanColor::Both => true,
+            HumanColor::None => false,
+        }
+    }
+}
+
+fn load_params(path: Option<&Path>) -> EvalParams {
+    match path {
+        Some(path) => EvalParams::load(path).expect("Valid parameter file"),
+        None => EvalParams::default(),
+    }
+}
+
+// A network takes precedence over parameters when both are given
+fn load_evaluator(params: Option<&Path>, network: Option<&Path>) -> Box<dyn Evaluator + Send> {
+    match network {
+        Some(path) => Box::new(NetworkEvaluator::new(Network::load(path).expect("Valid network file"))),
+        None => Box::new(load_params(params)),
+    }
+}
+
+// None if the side to play has no moves
+fn evaluate_iterative_deepening(node: &BreakthroughNode, depth: u32, evaluator: &mut dyn Evaluator, table: &mut TranspositionTable) -> Option<(BreakthroughMove, Evaluation)> {
     for d in 1..depth {
-        let eval = evaluate_with_ttable(node, d, table);
-        println!("{} {} {:?}", d, eval.0.to_string(), eval.1);
+        let eval = evaluate_with_ttable(node, d, evaluator, table)?;
+        println!("{} {} {:?}", d, eval.0, eval.1);
     }
+    let eval = evaluate_with_ttable(node, depth, evaluator, table)?;
     match node.to_play {
-        Player::White => evaluate_with_ttable(node, depth, table),
-        Player::Black => {
-            let eval = evaluate_with_ttable(node, depth, table);
-            (eval.0, -eval.1)
-        },
+        Player::White => Some(eval),
+        Player::Black => Some((eval.0, -eval.1)),
+    }
+}
+
+struct BookOptions {
+    book: Option<OpeningBook>,
+    random: bool,
+    rng: ChaCha12Rng,
+}
+
+impl BookOptions {
+    fn new(path: Option<&Path>, random: bool, seed: Option<u64>) -> Self {
+        Self {
+            book: path.map(|path| OpeningBook::load(path).expect("Valid book file")),
+            random,
+            rng: match seed {
+                Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+                None => ChaCha12Rng::from_entropy(),
+            },
+        }
+    }
+
+    fn probe(&mut self, node: &BreakthroughNode) -> Option<BreakthroughMove> {
+        let book = self.book.as_ref()?;
+        if self.random {
+            book.choose(node, &mut self.rng)
+        } else {
+            book.best(node)
+        }
     }
 }
 
-fn do_selfplay(strategy: PlayStrategy, depth: u32) {
+// Write out a finished game if a record file was asked for
+fn save_record(mut record: GameRecord, path: Option<&Path>) {
+    if let Some(path) = path {
+        record.finish();
+        record.append(path).expect("Writable record file");
+        println!("Saved game to {}", path.display());
+    }
+}
+
+fn do_selfplay(strategy: PlayStrategy, depth: u32, mut white: Box<dyn Evaluator>, mut black: Box<dyn Evaluator>, renderer: BoardRenderer, record_path: Option<&Path>, mut book: BookOptions) {
     match strategy {
         PlayStrategy::Random => todo!(),
         PlayStrategy::V2 => {
             let mut node = BreakthroughNode::default();
-            let mut table = TranspositionTable::new(2usize.pow(22));
+            let mut record = GameRecord::new(node.clone());
+            record.white = format!("v2 depth {}", depth);
+            record.black = format!("v2 depth {}", depth);
+            record.date = record::today();
+            // Each side gets its own table since their evaluations may differ
+            let mut white_table = TranspositionTable::new(2usize.pow(22));
+            let mut black_table = TranspositionTable::new(2usize.pow(22));
 
-            println!("{}\n{}", node.fen(), node.to_string());
+            show_board(&renderer, &node, None);
 
             while !node.is_terminal() {
-                let (action, eval) = evaluate_iterative_deepening(&node, depth, &mut table);
-                println!("({}, {:?})", action.to_string(), eval);
+                if let Some(action) = book.probe(&node) {
+                    println!("({}, book)", action);
+                    node = node.take_action(&action);
+                    record.push(action, None, Some("book".to_string()));
+                    show_board(&renderer, &node, Some(&action));
+                    continue;
+                }
+                let searched = match node.to_play {
+                    Player::White => evaluate_iterative_deepening(&node, depth, white.as_mut(), &mut white_table),
+                    Player::Black => evaluate_iterative_deepening(&node, depth, black.as_mut(), &mut black_table),
+                };
+                let Some((action, eval)) = searched else {
+                    println!("No legal moves for {:?}", node.to_play);
+                    break;
+                };
+                println!("({}, {:?})", action, eval);
                 node = node.take_action(&action);
-                println!("{}\n{}", node.fen(), node.to_string());
+                record.push(action, Some(eval), None);
+                show_board(&renderer, &node, Some(&action));
             }
+            save_record(record, record_path);
         },
     }
 }
 
-fn get_user_action(node: &BreakthroughNode) -> Result<BreakthroughMove, io::Error> {
-    let valid_moves = node.get_possible_actions();
+// What the user can type during a game besides a move
+enum PlayCommand {
+    Move(BreakthroughMove),
+    Undo,
+    Redo,
+    Jump(usize),
+    Go,
+    Fen,
+    Hint,
+    Flip,
+    Eval,
+    Save(Option<PathBuf>),
+    Help,
+}
+
+const PLAY_HELP: &str = "Commands: a move like d2d3, undo, redo, jump <ply>, go, fen, hint, flip, eval, save [path], help";
+
+fn parse_command(line: &str, node: &BreakthroughNode) -> Result<PlayCommand, String> {
+    let mut words = line.split_whitespace();
+    let command = match (words.next(), words.next()) {
+        (Some("undo"), None) => PlayCommand::Undo,
+        (Some("redo"), None) => PlayCommand::Redo,
+        (Some("jump"), Some(ply)) => PlayCommand::Jump(ply.parse().map_err(|_| format!("Invalid ply: {}", ply))?),
+        (Some("go"), None) => PlayCommand::Go,
+        (Some("fen"), None) => PlayCommand::Fen,
+        (Some("hint"), None) => PlayCommand::Hint,
+        (Some("flip"), None) => PlayCommand::Flip,
+        (Some("eval"), None) => PlayCommand::Eval,
+        (Some("save"), path) => PlayCommand::Save(path.map(PathBuf::from)),
+        (Some("help"), None) => PlayCommand::Help,
+        (Some(m), None) => {
+            let action = BreakthroughMove::from_str(m).map_err(|e| e.to_string())?;
+            node.check_move(&action).map_err(|reason| format!("Illegal move: {}", reason))?;
+            PlayCommand::Move(action)
+        },
+        _ => return Err(format!("Unknown command: {}", line.trim())),
+    };
+    match words.next() {
+        Some(_) => Err(format!("Unknown command: {}", line.trim())),
+        None => Ok(command),
+    }
+}
+
+// None once the input has run out
+fn get_user_command(node: &BreakthroughNode) -> Result<Option<PlayCommand>, io::Error> {
     let stdin = io::stdin();
     loop {
         let mut buffer = String::new();
-        stdin.read_line(&mut buffer)?;
-        match BreakthroughMove::from_str(&buffer.trim()) {
-            Ok(m) => if valid_moves.contains(&m) {
-                return Ok(m);
-            } else {
-                println!("Invalid move!");
-                continue
-            },
-            Err(e) => {
-                println!("{}", e.to_string());
-                continue
-            },
+        if stdin.read_line(&mut buffer)? == 0 {
+            return Ok(None);
+        }
+        if buffer.trim().is_empty() {
+            continue;
+        }
+        match parse_command(&buffer, node) {
+            Ok(command) => return Ok(Some(command)),
+            Err(message) => println!("{}", message),
+        }
+    }
+}
+
+fn show_board(renderer: &BoardRenderer, node: &BreakthroughNode, last_move: Option<&BreakthroughMove>) {
+    println!("{}\n{}", node.fen(), renderer.render(node, last_move));
+}
+
+// The game a play session starts from: a position and the moves already played from it
+fn start_game(fen: Option<&str>, moves: Option<&str>, human: &HumanColor, depth: u32) -> Game {
+    let start = match fen {
+        Some(fen) => BreakthroughNode::from_fen(fen).expect("Valid FEN"),
+        None => BreakthroughNode::default(),
+    };
+    let mut record = GameRecord::new(start);
+    let name = |player| if human.plays(&player) { "human".to_string() } else { format!("v2 depth {}", depth) };
+    record.white = name(Player::White);
+    record.black = name(Player::Black);
+    record.date = record::today();
+    let mut game = Game::from_record(record);
+    for step in moves.unwrap_or_default().split_whitespace() {
+        let action = BreakthroughMove::from_str(step).expect("Valid move");
+        if let Err(reason) = game.play(action, None, None) {
+            panic!("Illegal move {}: {}", step, reason);
         }
     }
+    game
+}
+
+// How a play session is set up, beyond the engine itself
+struct PlayOptions {
+    human: HumanColor,
+    renderer: BoardRenderer,
+    record: Option<PathBuf>,
 }
 
-fn do_play(strategy: PlayStrategy, depth: u32) {
+fn do_play(strategy: PlayStrategy, depth: u32, mut evaluator: Box<dyn Evaluator>, mut game: Game, options: PlayOptions, mut book: BookOptions) {
+    let PlayOptions { human, mut renderer, record } = options;
+    let record_path = record.as_deref();
     match strategy {
         PlayStrategy::Random => todo!(),
         PlayStrategy::V2 => {
-            let mut node = BreakthroughNode::default();
             let mut table = TranspositionTable::new(2usize.pow(22));
+            // Whoever plays Black alone sees the board from their side
+            renderer.flipped = human == HumanColor::Black;
 
-            println!("{}\n{}", node.fen(), node.to_string());
+            println!("{}", PLAY_HELP);
+            show_board(&renderer, game.node(), game.last_move());
+            // Set after moving through the history, so the engine doesn't play over
+            // the moves that can be redone until the user plays on or types go
+            let mut paused = false;
 
-            while !node.is_terminal() {
-                let action = get_user_action(&node).expect("Valid input");
-                node = node.take_action(&action);
-                println!("{}\n{}", node.fen(), node.to_string());
+            while !game.node().is_terminal() {
+                if !human.plays(&game.node().to_play) && !paused {
+                    let node = game.node().clone();
+                    let (action, eval) = match book.probe(&node) {
+                        Some(action) => {
+                            println!("({}, book)", action);
+                            (action, None)
+                        },
+                        None => match evaluate_iterative_deepening(&node, depth, evaluator.as_mut(), &mut table) {
+                            Some((action, eval)) => {
+                                println!("({}, {:?})", action, eval);
+                                (action, Some(eval))
+                            },
+                            None => {
+                                println!("No legal moves for {:?}", node.to_play);
+                                break;
+                            },
+                        },
+                    };
+                    let comment = if eval.is_none() { Some("book".to_string()) } else { None };
+                    game.play(action, eval, comment).expect("Engine move should be legal");
+                    show_board(&renderer, game.node(), game.last_move());
+                    continue;
+                }
 
-                let (action, eval) = evaluate_iterative_deepening(&node, depth, &mut table);
-                println!("({}, {:?})", action.to_string(), eval);
-                node = node.take_action(&action);
-                println!("{}\n{}", node.fen(), node.to_string());
+                let Some(command) = get_user_command(game.node()).expect("Valid input") else {
+                    break;
+                };
+                match command {
+                    PlayCommand::Move(_) if !human.plays(&game.node().to_play) => {
+                        println!("It's the engine's move, type go to let it play");
+                    },
+                    PlayCommand::Move(action) => {
+                        game.play(action, None, None).expect("Move was checked");
+                        paused = false;
+                        show_board(&renderer, game.node(), game.last_move());
+                    },
+                    // Take back the engine's replies too, so it's a human's move again
+                    PlayCommand::Undo => {
+                        if !game.undo() {
+                            println!("Nothing to undo");
+                            continue;
+                        }
+                        while !human.plays(&game.node().to_play) && game.undo() {}
+                        paused = true;
+                        show_board(&renderer, game.node(), game.last_move());
+                    },
+                    PlayCommand::Redo => {
+                        if !game.redo() {
+                            println!("Nothing to redo");
+                            continue;
+                        }
+                        while !human.plays(&game.node().to_play) && game.redo() {}
+                        paused = true;
+                        show_board(&renderer, game.node(), game.last_move());
+                    },
+                    PlayCommand::Jump(ply) => {
+                        if !game.jump(ply) {
+                            println!("The game only has {} plies", game.line_len());
+                            continue;
+                        }
+                        paused = true;
+                        show_board(&renderer, game.node(), game.last_move());
+                    },
+                    PlayCommand::Go => paused = false,
+                    PlayCommand::Fen => println!("{}", game.node().fen()),
+                    PlayCommand::Hint => match evaluate_with_ttable(game.node(), depth, evaluator.as_mut(), &mut table) {
+                        Some((action, eval)) => println!("Hint: {} ({:?})", action, eval),
+                        None => println!("No legal moves"),
+                    },
+                    PlayCommand::Flip => {
+                        renderer.flipped = !renderer.flipped;
+                        show_board(&renderer, game.node(), game.last_move());
+                    },
+                    PlayCommand::Eval => println!("Static eval for White: {:?}", evaluator.evaluate(game.node())),
+                    PlayCommand::Save(path) => match path.as_deref().or(record_path) {
+                        Some(path) => save_record(game.record(), Some(path)),
+                        None => println!("No record file given, use save <path>"),
+                    },
+                    PlayCommand::Help => println!("{}", PLAY_HELP),
+                }
             }
+            save_record(game.record(), record_path);
         },
     }
 }
 
+// Plays out a game between two evaluators and returns the winner
+fn play_game(start: &BreakthroughNode, depth: u32, white: &mut dyn Evaluator, black: &mut dyn Evaluator) -> Player {
+    let mut white_table = TranspositionTable::new(2usize.pow(18));
+    let mut black_table = TranspositionTable::new(2usize.pow(18));
+    datagen::play_out(start, |node| {
+        let searched = match node.to_play {
+            Player::White => evaluate_with_ttable(node, depth, white, &mut white_table),
+            Player::Black => evaluate_with_ttable(node, depth, black, &mut black_table),
+        };
+        searched.map(|(action, _)| action)
+    })
+}
+
+fn do_match(mut a: Box<dyn Evaluator>, mut b: Box<dyn Evaluator>, openings: u32, depth: u32, random_plies: u32, seed: u64) {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    let (mut a_wins, mut b_wins) = (0, 0);
+    for i in 0..openings {
+        let start = random_opening(random_plies, &mut rng);
+        let first = play_game(&start, depth, a.as_mut(), b.as_mut());
+        let second = play_game(&start, depth, b.as_mut(), a.as_mut());
+        a_wins += (first == Player::White) as u32 + (second == Player::Black) as u32;
+        b_wins += (first == Player::Black) as u32 + (second == Player::White) as u32;
+        println!("{} {} {:?} {:?} (a={}, b={})", i, start.fen(), first, second, a_wins, b_wins);
+    }
+    println!("a={} b={}", a_wins, b_wins);
+}
+
+fn do_tune(data: &Path, initial: EvalParams, output: &Path, passes: usize) {
+    let positions = tuning::read_positions(data).expect("Valid positions file");
+    println!("Loaded {} positions", positions.len());
+
+    let scale = tuning::fit_scale(&positions, &initial);
+    let before = tuning::loss(&positions, &initial, scale);
+    println!("Scale {:.1}, loss before {:.6}", scale, before);
+
+    let tuned = tuning::tune(&positions, &initial, scale, passes);
+    let after = tuning::loss(&positions, &tuned, scale);
+    println!("Loss after {:.6}\n{}", after, tuned);
+    tuned.save(output).expect("Writable output file");
+}
+
 fn main() {
     let args = Cli::parse();
 
     match args.command {
-        Commands::Selfplay { strategy, depth } => {
-            do_selfplay(strategy, depth);
+        Commands::Selfplay { strategy, depth, white_params, black_params, white_network, black_network, render, record, book, book_random, seed } => {
+            let white = load_evaluator(white_params.as_deref(), white_network.as_deref());
+            let black = load_evaluator(black_params.as_deref(), black_network.as_deref());
+            do_selfplay(strategy, depth, white, black, render.renderer(), record.as_deref(), BookOptions::new(book.as_deref(), book_random, seed));
+        },
+        Commands::Play { strategy, depth, params, network, human_color, fen, moves, render, record, book, book_random, seed } => {
+            let book = BookOptions::new(book.as_deref(), book_random, seed);
+            let game = start_game(fen.as_deref(), moves.as_deref(), &human_color, depth);
+            let options = PlayOptions { human: human_color, renderer: render.renderer(), record };
+            do_play(strategy, depth, load_evaluator(params.as_deref(), network.as_deref()), game, options, book);
+        },
+        #[cfg(feature = "tui")]
+        Commands::Tui { depth, time_ms, params, network, human_color, fen, moves, record } => {
+            let game = start_game(fen.as_deref(), moves.as_deref(), &human_color, depth);
+            let options = TuiOptions {
+                human: human_color,
+                limits: v2::search::searcher::SearchLimits { depth, time: time_ms.map(std::time::Duration::from_millis) },
+                table_size: 2usize.pow(22),
+            };
+            let game = tui::run(game, load_evaluator(params.as_deref(), network.as_deref()), options).expect("Working terminal");
+            save_record(game.record(), record.as_deref());
+        },
+        #[cfg(feature = "serve")]
+        Commands::Serve { address, port, threads, depth, max_depth, params, network } => {
+            let options = ServeOptions { threads, depth, max_depth, table_size: 2usize.pow(22) };
+            serve::run(&format!("{}:{}", address, port), options, || load_evaluator(params.as_deref(), network.as_deref()))
+                .expect("Address to listen on");
+        },
+        Commands::Match { a, b, a_network, b_network, openings, depth, random_plies, seed } => {
+            let a = load_evaluator(a.as_deref(), a_network.as_deref());
+            let b = load_evaluator(b.as_deref(), b_network.as_deref());
+            do_match(a, b, openings, depth, random_plies, seed);
+        },
+        Commands::Explain { fen, params } => {
+            let node = match fen {
+                Some(fen) => BreakthroughNode::from_fen(&fen).expect("Valid FEN"),
+                None => BreakthroughNode::default(),
+            };
+            println!("{}\n{}\n", node.fen(), node);
+            println!("{}", explain(&node, &load_params(params.as_deref())));
+        },
+        Commands::Diagram { fen, arrows, highlight, flip, output } => {
+            let node = match fen {
+                Some(fen) => BreakthroughNode::from_fen(&fen).expect("Valid FEN"),
+                None => BreakthroughNode::default(),
+            };
+            let options = DiagramOptions {
+                flipped: flip,
+                highlights: highlight.unwrap_or_default().split_whitespace()
+                    .map(|square| BreakthroughMove::parse_square(square).expect("Valid square"))
+                    .collect(),
+                arrows: arrows.unwrap_or_default().split_whitespace()
+                    .map(|step| BreakthroughMove::from_str(step).expect("Valid move"))
+                    .collect(),
+            };
+            fs::write(&output, svg_diagram(&node, &options)).expect("Writable output file");
+        },
+        Commands::Book { records, output, max_plies, min_weight } => {
+            let mut games = Vec::new();
+            for path in records {
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some("sgf") => games.extend(sgf::load_sgf(&path).expect("Valid SGF file")),
+                    _ => games.extend(GameRecord::load_all(&path).expect("Valid record file")),
+                }
+            }
+            let mut book = OpeningBook::from_records(&games, max_plies);
+            book.prune(min_weight);
+            book.save(&output).expect("Writable output file");
+            println!("Built a book of {} positions from {} games", book.len(), games.len());
+        },
+        Commands::Import { sgf, output } => {
+            let records = sgf::load_sgf(&sgf).expect("Valid SGF file");
+            for record in &records {
+                record.append(&output).expect("Writable record file");
+            }
+            println!("Imported {} games", records.len());
+        },
+        Commands::Datagen { output, games, depth, random_plies, threads, seed, params, network } => {
+            let config = DatagenConfig { depth, random_plies, ..DatagenConfig::default() };
+            let mut writer = BufWriter::new(File::create(output).expect("Writable output file"));
+            let written = datagen::generate(&config, games, threads, seed, || load_evaluator(params.as_deref(), network.as_deref()), &mut writer)
+                .expect("Writable output file");
+            println!("Wrote {} positions from {} games", written, games);
+        },
+        Commands::Tune { data, params, output, passes } => {
+            do_tune(&data, load_params(params.as_deref()), &output, passes);
+        },
+        Commands::Params { params, output } => {
+            let params = load_params(params.as_deref());
+            match output {
+                Some(path) => params.save(&path).expect("Writable output file"),
+                None => print!("{}", params),
+            }
         },
-        Commands::Play { strategy, depth } => {
-            do_play(strategy, depth);
-        }
     }
 }
@@ -0,0 +1,292 @@
+use std::{
+    io,
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+use v2::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, GameResult},
+    evaluation::Evaluator,
+    search::{
+        searcher::{SearchLimits, Searcher},
+        table::SharedTable,
+    },
+};
+
+/*
+ * Local HTTP server with a JSON API for front-ends. Every endpoint takes a POST with
+ * a JSON object, where `fen` defaults to the starting position:
+ *
+ *   /moves    {fen}                   legal moves and the result
+ *   /apply    {fen, move}             the position after a move
+ *   /analyze  {fen, depth?, time_ms?} best move, eval and PV
+ *   /play     {fen, depth?, time_ms?} the engine's move and the position after it
+ *
+ * Moves, players and evals are in the serde forms of the core types, so evals look
+ * like {"type": "heuristic", "value": 40}, from White's point of view.
+ *
+ * Requests are handled by a pool of worker threads, each with its own evaluator. The
+ * workers share one transposition table, so analysis from one request helps the next.
+ * A request that panics gets a 500 and the worker carries on with the next one.
+ */
+
+pub struct ServeOptions {
+    pub threads: usize,
+    // Used when a request doesn't give a depth
+    pub depth: u32,
+    // Deeper requests are searched to this depth instead
+    pub max_depth: u32,
+    pub table_size: usize,
+}
+
+// Status code and message for a request that can't be answered
+#[derive(Debug)]
+struct ApiError(u16, String);
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self(400, message.into())
+    }
+}
+
+pub fn run<F>(address: &str, options: ServeOptions, make_evaluator: F) -> io::Result<()>
+where
+    F: Fn() -> Box<dyn Evaluator + Send>,
+{
+    let server = Arc::new(Server::http(address).map_err(io::Error::other)?);
+    let table = SharedTable::new(options.table_size);
+    println!("Listening on http://{}", server.server_addr());
+
+    let workers: Vec<_> = (0..options.threads.max(1))
+        .map(|_| {
+            let server = server.clone();
+            let mut worker = Worker {
+                evaluator: make_evaluator(),
+                searcher: Searcher::with_table(table.clone()),
+                depth: options.depth.min(options.max_depth),
+                max_depth: options.max_depth,
+            };
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    worker.respond(request);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+struct Worker {
+    evaluator: Box<dyn Evaluator + Send>,
+    searcher: Searcher<SharedTable>,
+    depth: u32,
+    max_depth: u32,
+}
+
+impl Worker {
+    fn respond(&mut self, mut request: Request) {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.handle(&mut request)))
+            .unwrap_or_else(|_| Err(ApiError(500, "The engine failed on this request".to_string())));
+        let (status, body) = match result {
+            Ok(body) => (200, body),
+            Err(ApiError(status, message)) => (status, json!({ "error": message })),
+        };
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header("Content-Type", "application/json"))
+            .with_header(header("Access-Control-Allow-Origin", "*"))
+            .with_header(header("Access-Control-Allow-Headers", "Content-Type"));
+        // The client may have gone away, there's nobody left to tell
+        let _ = request.respond(response);
+    }
+
+    fn handle(&mut self, request: &mut Request) -> Result<Value, ApiError> {
+        match request.method() {
+            Method::Post => {}
+            // Browsers check before posting JSON from another origin
+            Method::Options => return Ok(Value::Null),
+            _ => return Err(ApiError(405, "Use POST".to_string())),
+        }
+        let path = request.url().split('?').next().unwrap_or_default().to_string();
+        let mut body = String::new();
+        request
+            .as_reader()
+            .read_to_string(&mut body)
+            .map_err(|e| ApiError::bad_request(format!("Unreadable body: {}", e)))?;
+        self.answer(&path, &parse_body(&body)?)
+    }
+
+    // The response to a request body, without any HTTP, so it can be tested directly
+    fn answer(&mut self, endpoint: &str, body: &Value) -> Result<Value, ApiError> {
+        if !matches!(endpoint, "/moves" | "/apply" | "/analyze" | "/play") {
+            return Err(ApiError(404, format!("No endpoint {}", endpoint)));
+        }
+        let node = match body.get("fen") {
+            None | Some(Value::Null) => BreakthroughNode::default(),
+            Some(Value::String(fen)) => {
+                BreakthroughNode::from_fen(fen).map_err(|e| ApiError::bad_request(format!("Invalid FEN: {}", e)))?
+            }
+            Some(_) => return Err(ApiError::bad_request("`fen` must be a string")),
+        };
+
+        match endpoint {
+            "/moves" => Ok(position_json(&node)),
+            "/apply" => {
+                let action = match body.get("move") {
+                    Some(Value::String(action)) => BreakthroughMove::from_str(action)
+                        .map_err(|e| ApiError::bad_request(format!("Invalid move: {}", e)))?,
+                    _ => return Err(ApiError::bad_request("`move` must be a string like \"d2d3\"")),
+                };
+                node.check_move(&action).map_err(|e| ApiError::bad_request(format!("Illegal move {}: {}", action, e)))?;
+                Ok(position_json(&node.take_action(&action)))
+            }
+            _ => {
+                let limits = self.limits(body)?;
+                if node.get_result() != GameResult::Undecided {
+                    return Err(ApiError::bad_request("The game is over"));
+                }
+                if node.moves().is_empty() {
+                    return Err(ApiError::bad_request("No legal moves"));
+                }
+                let info = self
+                    .searcher
+                    .search(&node, limits, &mut *self.evaluator, None, |_| {})
+                    .ok_or_else(|| ApiError(500, "The search found no move".to_string()))?;
+                let elapsed_ms = info.elapsed.as_millis() as u64;
+                match endpoint {
+                    "/analyze" => Ok(json!({
+                        "best": info.best,
+                        "eval": info.eval,
+                        "depth": info.depth,
+                        "pv": info.pv,
+                        "elapsed_ms": elapsed_ms,
+                    })),
+                    _ => {
+                        let mut played = position_json(&node.take_action(&info.best));
+                        played["move"] = json!(info.best);
+                        played["eval"] = json!(info.eval);
+                        played["depth"] = json!(info.depth);
+                        Ok(played)
+                    }
+                }
+            }
+        }
+    }
+
+    fn limits(&self, body: &Value) -> Result<SearchLimits, ApiError> {
+        let field = |name: &str| match body.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .map(Some)
+                .ok_or_else(|| ApiError::bad_request(format!("`{}` must be a positive integer", name))),
+        };
+        let depth = match field("depth")? {
+            Some(0) => return Err(ApiError::bad_request("`depth` must be at least 1")),
+            Some(depth) => depth.min(self.max_depth as u64) as u32,
+            None => self.depth,
+        };
+        Ok(SearchLimits { depth, time: field("time_ms")?.map(Duration::from_millis) })
+    }
+}
+
+// An empty body is the same as `{}`
+fn parse_body(body: &str) -> Result<Value, ApiError> {
+    match body.trim() {
+        "" => Ok(json!({})),
+        body => serde_json::from_str(body).map_err(|e| ApiError::bad_request(format!("Invalid JSON: {}", e))),
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("Valid header")
+}
+
+fn position_json(node: &BreakthroughNode) -> Value {
+    let (winner, moves) = match node.get_result() {
+        GameResult::Win(player) => (Some(player), Vec::new()),
+        GameResult::Undecided => (None, node.moves().to_vec()),
+    };
+    json!({
+        "fen": node.fen(),
+        "to_play": node.to_play,
+        "winner": winner,
+        "moves": moves,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+    use v2::{evaluation::params::EvalParams, search::searcher::Searcher, search::table::SharedTable};
+
+    use super::{parse_body, ApiError, Worker};
+
+    fn worker() -> Worker {
+        Worker {
+            evaluator: Box::new(EvalParams::default()),
+            searcher: Searcher::with_table(SharedTable::new(1 << 12)),
+            depth: 2,
+            max_depth: 3,
+        }
+    }
+
+    fn status(result: Result<Value, ApiError>) -> u16 {
+        result.map(|_| 200).unwrap_or_else(|ApiError(status, _)| status)
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let mut worker = worker();
+        let start = worker.answer("/moves", &parse_body("").unwrap()).unwrap();
+        assert_eq!(start["to_play"], "white");
+        assert_eq!(start["moves"].as_array().unwrap().len(), 22);
+
+        let applied = worker.answer("/apply", &json!({ "fen": start["fen"], "move": "d2d3" })).unwrap();
+        assert_eq!(applied["fen"], "bbbbbbbb/bbbbbbbb/8/8/8/3w4/www1wwww/wwwwwwww b 1");
+
+        let analysis = worker.answer("/analyze", &json!({ "depth": 2 })).unwrap();
+        assert_eq!(analysis["depth"], 2);
+        assert_eq!(analysis["eval"]["type"], "heuristic");
+        assert_eq!(analysis["pv"][0], analysis["best"]);
+
+        let played = worker.answer("/play", &json!({ "fen": applied["fen"] })).unwrap();
+        assert_eq!(played["to_play"], "white");
+        assert_eq!(played["depth"], 2);
+
+        // Deeper requests are clamped to the server's limit
+        let clamped = worker.answer("/analyze", &json!({ "depth": 1000 })).unwrap();
+        assert_eq!(clamped["depth"], 3);
+        assert_eq!(status(worker.answer("/nowhere", &json!({}))), 404);
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let mut worker = worker();
+        assert_eq!(status(parse_body("{\"fen\":")), 400);
+        assert_eq!(status(worker.answer("/moves", &json!({ "fen": "8/8 w 1" }))), 400);
+        assert_eq!(status(worker.answer("/moves", &json!({ "fen": 3 }))), 400);
+        assert_eq!(status(worker.answer("/apply", &json!({ "move": "d2d4" }))), 400);
+        assert_eq!(status(worker.answer("/apply", &json!({ "move": "d2" }))), 400);
+        assert_eq!(status(worker.answer("/analyze", &json!({ "depth": 0 }))), 400);
+        assert_eq!(status(worker.answer("/analyze", &json!({ "time_ms": -1 }))), 400);
+
+        let won = json!({ "fen": "w7/8/8/8/8/8/7b/8 b 2" });
+        assert_eq!(worker.answer("/moves", &won).unwrap()["winner"], "white");
+        assert_eq!(status(worker.answer("/analyze", &won)), 400);
+        assert_eq!(status(worker.answer("/play", &won)), 400);
+
+        // White has no pieces left, so there's nothing to search
+        let stuck = json!({ "fen": "b7/8/8/8/8/8/8/8 w 1" });
+        assert_eq!(status(worker.answer("/analyze", &stuck)), 400);
+        assert_eq!(status(worker.answer("/play", &stuck)), 400);
+    }
+}
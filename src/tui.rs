@@ -0,0 +1,587 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use ratatui::{
+    crossterm::{
+        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind},
+        execute,
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use v2::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, GameResult, Player},
+    evaluation::Evaluator,
+    game::Game,
+    search::searcher::{SearchInfo, SearchLimits, Searcher},
+};
+
+use crate::HumanColor;
+
+/*
+ * Full-screen terminal UI for playing and analysis. The engine searches on its own
+ * thread and reports every finished depth, so the eval and PV panel fills in while
+ * it thinks. Moves are entered by typing them, with the arrow keys, or by clicking.
+ */
+
+const HELP: &str = "type d2d3 / arrows+enter / click: move   u r: undo redo   m: engine move   s: stop   p: analysis   v: flip   q: quit";
+
+// Deep enough that analysis runs until it's stopped or finds a win
+const ANALYSIS_DEPTH: u32 = 64;
+
+const LIGHT_SQUARE: Color = Color::Rgb(240, 217, 181);
+const DARK_SQUARE: Color = Color::Rgb(181, 136, 99);
+const LAST_MOVE: Color = Color::Rgb(205, 210, 106);
+const CURSOR: Color = Color::Rgb(100, 149, 237);
+const SELECTED: Color = Color::Rgb(106, 168, 79);
+const TARGET: Color = Color::Rgb(170, 210, 140);
+
+pub struct TuiOptions {
+    pub human: HumanColor,
+    pub limits: SearchLimits,
+    pub table_size: usize,
+}
+
+// A search for the engine thread to run
+struct Job {
+    id: u64,
+    node: BreakthroughNode,
+    limits: SearchLimits,
+    stop: Arc<AtomicBool>,
+}
+
+enum EngineEvent {
+    Info(u64, SearchInfo),
+    Done(u64, Option<SearchInfo>),
+}
+
+fn spawn_engine(mut evaluator: Box<dyn Evaluator + Send>, table_size: usize) -> (Sender<Job>, Receiver<EngineEvent>) {
+    let (job_sender, jobs) = mpsc::channel::<Job>();
+    let (event_sender, events) = mpsc::channel();
+    thread::spawn(move || {
+        let mut searcher = Searcher::new(table_size);
+        for job in jobs {
+            let result = searcher.search(&job.node, job.limits, evaluator.as_mut(), Some(&job.stop), |info| {
+                let _ = event_sender.send(EngineEvent::Info(job.id, info.clone()));
+            });
+            if event_sender.send(EngineEvent::Done(job.id, result)).is_err() {
+                break;
+            }
+        }
+    });
+    (job_sender, events)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum JobKind {
+    Move,
+    Analysis,
+}
+
+struct Thinking {
+    id: u64,
+    kind: JobKind,
+    stop: Arc<AtomicBool>,
+}
+
+struct App {
+    game: Game,
+    human: HumanColor,
+    limits: SearchLimits,
+    jobs: Sender<Job>,
+    next_id: u64,
+    thinking: Option<Thinking>,
+    // Latest engine result for the current position
+    info: Option<SearchInfo>,
+    analysis: bool,
+    analysis_done: bool,
+    // Engine moves for the side to play even if a human plays it
+    force_move: bool,
+    // Set after moving through the history, so the engine doesn't play over the
+    // moves that can be redone until the user plays on or presses m
+    paused: bool,
+    flipped: bool,
+    cursor: u8,
+    selected: Option<u8>,
+    input: String,
+    message: String,
+    board_area: Rect,
+    quit: bool,
+}
+
+// Run the UI until the user quits, returning the game as it was left
+pub fn run(game: Game, evaluator: Box<dyn Evaluator + Send>, options: TuiOptions) -> io::Result<Game> {
+    let (jobs, events) = spawn_engine(evaluator, options.table_size);
+    let mut app = App::new(game, jobs, options);
+
+    let mut terminal = ratatui::init();
+    let result = execute!(io::stdout(), EnableMouseCapture).and_then(|_| app.run(&mut terminal, &events));
+    let _ = execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+    app.stop();
+    result.map(|_| app.game)
+}
+
+impl App {
+    fn new(game: Game, jobs: Sender<Job>, options: TuiOptions) -> Self {
+        Self {
+            game,
+            flipped: options.human == HumanColor::Black,
+            human: options.human,
+            limits: options.limits,
+            jobs,
+            next_id: 0,
+            thinking: None,
+            info: None,
+            analysis: false,
+            analysis_done: false,
+            force_move: false,
+            paused: false,
+            cursor: 52,
+            selected: None,
+            input: String::new(),
+            message: String::new(),
+            board_area: Rect::default(),
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, events: &Receiver<EngineEvent>) -> io::Result<()> {
+        while !self.quit {
+            self.schedule();
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(Duration::from_millis(50))? {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key(key.code),
+                    Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                        self.on_click(mouse.column, mouse.row)
+                    },
+                    _ => {},
+                }
+            }
+            while let Ok(event) = events.try_recv() {
+                self.on_engine(event);
+            }
+        }
+        Ok(())
+    }
+
+    fn engine_to_move(&self) -> bool {
+        (!self.human.plays(&self.game.node().to_play) && !self.paused) || self.force_move
+    }
+
+    // The winner once the game is over, which includes the side to play having no moves
+    fn winner(&self) -> Option<Player> {
+        let node = self.game.node();
+        match node.get_result() {
+            GameResult::Win(player) => Some(player),
+            GameResult::Undecided if node.moves().is_empty() => Some(match node.to_play {
+                Player::White => Player::Black,
+                Player::Black => Player::White,
+            }),
+            GameResult::Undecided => None,
+        }
+    }
+
+    // Start whatever search the position calls for, if it isn't running already
+    fn schedule(&mut self) {
+        if self.winner().is_some() {
+            return;
+        }
+        let kind = if self.engine_to_move() {
+            JobKind::Move
+        } else if self.analysis && !self.analysis_done {
+            JobKind::Analysis
+        } else {
+            return;
+        };
+        match &self.thinking {
+            Some(thinking) if thinking.kind == kind => return,
+            Some(_) => self.stop(),
+            None => {},
+        }
+        let limits = match kind {
+            JobKind::Move => self.limits,
+            JobKind::Analysis => SearchLimits { depth: ANALYSIS_DEPTH, time: None },
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        self.next_id += 1;
+        let job = Job { id: self.next_id, node: self.game.node().clone(), limits, stop: stop.clone() };
+        if self.jobs.send(job).is_ok() {
+            self.thinking = Some(Thinking { id: self.next_id, kind, stop });
+        }
+    }
+
+    // Stop the running search and forget about it, its results are ignored
+    fn stop(&mut self) {
+        if let Some(thinking) = self.thinking.take() {
+            thinking.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn on_engine(&mut self, event: EngineEvent) {
+        let current = self.thinking.as_ref().map(|thinking| (thinking.id, thinking.kind));
+        match event {
+            EngineEvent::Info(id, info) if current.is_some_and(|(current, _)| current == id) => self.info = Some(info),
+            EngineEvent::Done(id, result) if current.is_some_and(|(current, _)| current == id) => {
+                self.thinking = None;
+                match (current.map(|(_, kind)| kind), result) {
+                    (Some(JobKind::Move), Some(info)) => {
+                        self.force_move = false;
+                        if self.game.play(info.best, Some(info.eval), None).is_ok() {
+                            self.message = format!("Engine played {} ({})", info.best, format_eval(&info.eval));
+                            self.position_changed();
+                        }
+                    },
+                    (Some(JobKind::Move), None) => self.force_move = false,
+                    _ => self.analysis_done = true,
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn history_moved(&mut self) {
+        self.position_changed();
+        self.paused = true;
+        if !self.human.plays(&self.game.node().to_play) && self.winner().is_none() {
+            self.message = "Paused on the engine's move, m to let it play".to_string();
+        }
+    }
+
+    fn position_changed(&mut self) {
+        self.stop();
+        self.info = None;
+        self.analysis_done = false;
+        self.selected = None;
+        self.input.clear();
+    }
+
+    fn on_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c @ ('a'..='h' | '1'..='8')) => {
+                self.input.push(c);
+                if self.input.len() == 4 {
+                    let input = std::mem::take(&mut self.input);
+                    match input.parse::<BreakthroughMove>() {
+                        Ok(action) => self.play(action),
+                        Err(e) => self.message = e.to_string(),
+                    }
+                }
+            },
+            KeyCode::Backspace => {
+                self.input.pop();
+            },
+            KeyCode::Esc => {
+                self.input.clear();
+                self.selected = None;
+            },
+            KeyCode::Enter | KeyCode::Char(' ') if self.input.is_empty() => self.select(self.cursor),
+            KeyCode::Enter => self.message = format!("Incomplete move: {}", self.input),
+            KeyCode::Up => self.move_cursor(-1, 0),
+            KeyCode::Down => self.move_cursor(1, 0),
+            KeyCode::Left => self.move_cursor(0, -1),
+            KeyCode::Right => self.move_cursor(0, 1),
+            // Take back the engine's replies too, so it's a human's move again
+            KeyCode::Char('u') if self.game.undo() => {
+                while !self.human.plays(&self.game.node().to_play) && self.game.undo() {}
+                self.force_move = false;
+                self.history_moved();
+            },
+            KeyCode::Char('r') if self.game.redo() => {
+                while !self.human.plays(&self.game.node().to_play) && self.game.redo() {}
+                self.history_moved();
+            },
+            KeyCode::Home => {
+                self.game.jump(0);
+                self.history_moved();
+            },
+            KeyCode::End => {
+                self.game.jump(self.game.line_len());
+                self.history_moved();
+            },
+            KeyCode::Char('m') => {
+                self.force_move = true;
+                self.paused = false;
+            },
+            // A move search that is stopped plays its best move so far
+            KeyCode::Char('s') => {
+                if let Some(thinking) = &self.thinking {
+                    thinking.stop.store(true, Ordering::Relaxed);
+                }
+                self.analysis = false;
+            },
+            KeyCode::Char('p') => {
+                self.analysis = !self.analysis;
+                if !self.analysis && self.thinking.as_ref().is_some_and(|t| t.kind == JobKind::Analysis) {
+                    self.stop();
+                }
+            },
+            KeyCode::Char('v') => self.flipped = !self.flipped,
+            KeyCode::Char('q') => self.quit = true,
+            _ => {},
+        }
+    }
+
+    // Rows and columns as drawn, so the arrow keys follow the screen when flipped
+    fn move_cursor(&mut self, rows: i32, cols: i32) {
+        let (row, col) = self.to_screen(self.cursor);
+        let row = (row as i32 + rows).clamp(0, 7) as u8;
+        let col = (col as i32 + cols).clamp(0, 7) as u8;
+        self.cursor = self.square_at(row, col);
+    }
+
+    fn to_screen(&self, square: u8) -> (u8, u8) {
+        match self.flipped {
+            false => (square / 8, square % 8),
+            true => (7 - square / 8, 7 - square % 8),
+        }
+    }
+
+    fn square_at(&self, row: u8, col: u8) -> u8 {
+        match self.flipped {
+            false => row * 8 + col,
+            true => (7 - row) * 8 + (7 - col),
+        }
+    }
+
+    fn on_click(&mut self, x: u16, y: u16) {
+        // Inside the border and past the rank labels, each square is three cells wide
+        let (left, top) = (self.board_area.x + 3, self.board_area.y + 1);
+        if x < left || y < top || x >= left + 24 || y >= top + 8 {
+            return;
+        }
+        self.cursor = self.square_at((y - top) as u8, ((x - left) / 3) as u8);
+        self.select(self.cursor);
+    }
+
+    // Pick up a piece, or put the picked up piece down
+    fn select(&mut self, square: u8) {
+        let node = self.game.node();
+        let own = match node.to_play {
+            Player::White => node.bitboard_white,
+            Player::Black => node.bitboard_black,
+        };
+        match self.selected {
+            Some(from) if from == square => self.selected = None,
+            _ if own & (1 << square) != 0 => self.selected = Some(square),
+            Some(from) => self.play(BreakthroughMove(from, square)),
+            None => self.message = "Pick one of the pieces to move first".to_string(),
+        }
+    }
+
+    fn play(&mut self, action: BreakthroughMove) {
+        if !self.human.plays(&self.game.node().to_play) {
+            self.message = "It's the engine's move".to_string();
+            return;
+        }
+        match self.game.play(action, None, None) {
+            Ok(()) => {
+                self.paused = false;
+                self.message.clear();
+                self.position_changed();
+            },
+            Err(reason) => {
+                self.message = format!("Illegal move {}: {}", action, reason);
+                self.selected = None;
+            },
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, bottom] = Layout::vertical([Constraint::Min(11), Constraint::Length(4)]).areas(frame.area());
+        let [board, side] = Layout::horizontal([Constraint::Length(28), Constraint::Min(30)]).areas(top);
+        let [engine, moves] = Layout::vertical([Constraint::Length(8), Constraint::Min(3)]).areas(side);
+        self.board_area = board;
+
+        frame.render_widget(Paragraph::new(self.board_lines()).block(Block::bordered().title(" Board ")), board);
+        frame.render_widget(
+            Paragraph::new(self.engine_lines()).wrap(Wrap { trim: true }).block(Block::bordered().title(" Engine ")),
+            engine,
+        );
+        let move_lines = self.move_lines();
+        let visible = moves.height.saturating_sub(2) as usize;
+        let skip = move_lines.len().saturating_sub(visible);
+        frame.render_widget(
+            Paragraph::new(move_lines.into_iter().skip(skip).collect::<Vec<_>>()).block(Block::bordered().title(" Moves ")),
+            moves,
+        );
+        let status = match (self.input.is_empty(), self.message.is_empty()) {
+            (false, _) => format!("> {}", self.input),
+            (true, false) => self.message.clone(),
+            (true, true) => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![Line::from(HELP), Line::from(status)]).block(Block::bordered()),
+            bottom,
+        );
+    }
+
+    fn board_lines(&self) -> Vec<Line<'static>> {
+        let node = self.game.node();
+        let last_move = self.game.last_move().copied();
+        let targets: Vec<u8> = match self.selected {
+            Some(from) => node.moves().iter().filter(|m| m.0 == from).map(|m| m.1).collect(),
+            None => Vec::new(),
+        };
+        let mut lines = Vec::new();
+        for row in 0..8 {
+            let rank = self.square_at(row, 0) / 8;
+            let mut spans = vec![Span::raw(format!("{} ", (b'8' - rank) as char))];
+            for col in 0..8 {
+                let square = self.square_at(row, col);
+                let background = if self.selected == Some(square) {
+                    SELECTED
+                } else if square == self.cursor {
+                    CURSOR
+                } else if targets.contains(&square) {
+                    TARGET
+                } else if last_move.is_some_and(|m| m.0 == square || m.1 == square) {
+                    LAST_MOVE
+                } else if (square / 8 + square % 8).is_multiple_of(2) {
+                    LIGHT_SQUARE
+                } else {
+                    DARK_SQUARE
+                };
+                let style = Style::new().bg(background).add_modifier(Modifier::BOLD);
+                let span = if node.bitboard_white & (1 << square) != 0 {
+                    Span::styled(" ● ", style.fg(Color::White))
+                } else if node.bitboard_black & (1 << square) != 0 {
+                    Span::styled(" ● ", style.fg(Color::Black))
+                } else {
+                    Span::styled("   ", style)
+                };
+                spans.push(span);
+            }
+            lines.push(Line::from(spans));
+        }
+        let files: String = (0..8).map(|col| format!(" {} ", (b'a' + self.square_at(0, col) % 8) as char)).collect();
+        lines.push(Line::from(format!("  {}", files)));
+        lines
+    }
+
+    fn engine_lines(&self) -> Vec<Line<'static>> {
+        let node = self.game.node();
+        let mut lines = vec![Line::from(match self.winner() {
+            Some(player) if node.is_terminal() => format!("{:?} wins", player),
+            Some(player) => format!("{:?} wins, {:?} has no moves", player, node.to_play),
+            None => format!("{:?} to play, move {}", node.to_play, (node.ply + 2) / 2),
+        })];
+        lines.push(Line::from(match self.thinking.as_ref().map(|t| t.kind) {
+            Some(JobKind::Move) => "Thinking about a move".to_string(),
+            Some(JobKind::Analysis) => "Analysing".to_string(),
+            None if self.analysis => "Analysis done".to_string(),
+            None => "Idle".to_string(),
+        }));
+        if let Some(info) = &self.info {
+            lines.push(Line::from(format!(
+                "Depth {}  eval {}  {:.1}s",
+                info.depth,
+                format_eval(&info.eval),
+                info.elapsed.as_secs_f64()
+            )));
+            let pv: Vec<String> = info.pv.iter().map(|m| m.to_string()).collect();
+            lines.push(Line::from(format!("PV {}", pv.join(" "))));
+        }
+        lines
+    }
+
+    // Numbered like a record, with moves that can be redone greyed out
+    fn move_lines(&self) -> Vec<Line<'static>> {
+        let start_ply = self.game.start().ply;
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, recorded) in self.game.line().iter().enumerate() {
+            let ply = start_ply + i as u32;
+            let style = if i < self.game.ply() {
+                Style::new()
+            } else {
+                Style::new().fg(Color::DarkGray)
+            };
+            if ply.is_multiple_of(2) || i == 0 {
+                let dots = if ply.is_multiple_of(2) { "" } else { " ..." };
+                lines.push(Line::from(format!("{:>3}.{}", ply / 2 + 1, dots)));
+            }
+            if let Some(line) = lines.last_mut() {
+                line.push_span(Span::styled(format!(" {}", recorded.action), style));
+            }
+        }
+        lines
+    }
+}
+
+fn format_eval(eval: &Evaluation) -> String {
+    match eval {
+        Evaluation::Heuristic(n) => format!("{:+}", n),
+        Evaluation::WhiteWinPly(ply) => format!("White wins by ply {}", ply),
+        Evaluation::BlackWinPly(ply) => format!("Black wins by ply {}", ply),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc::{self, Receiver};
+
+    use v2::{
+        core::{node8::BreakthroughNode, Player},
+        game::Game,
+        search::searcher::SearchLimits,
+    };
+
+    use crate::HumanColor;
+
+    use ratatui::crossterm::event::KeyCode;
+
+    use super::{App, Job, TuiOptions};
+
+    fn new_app(fen: &str, human: HumanColor) -> (App, Receiver<Job>) {
+        let (jobs, sent) = mpsc::channel();
+        let options = TuiOptions { human, limits: SearchLimits { depth: 2, time: None }, table_size: 1 << 10 };
+        (App::new(Game::new(BreakthroughNode::from_fen(fen).unwrap()), jobs, options), sent)
+    }
+
+    #[test]
+    fn test_no_search_once_over() {
+        // The engine has White, which has no pieces left to move
+        let (mut app, sent) = new_app("b7/8/8/8/8/8/8/8 w 1", HumanColor::Black);
+        app.schedule();
+        assert!(sent.try_recv().is_err());
+        assert_eq!(app.winner(), Some(Player::Black));
+
+        let (mut app, sent) = new_app("w7/8/8/8/8/8/7b/8 b 2", HumanColor::White);
+        app.schedule();
+        assert!(sent.try_recv().is_err());
+        assert_eq!(app.winner(), Some(Player::White));
+
+        let (mut app, sent) = new_app("b7/8/8/8/8/8/7w/8 w 1", HumanColor::Black);
+        app.schedule();
+        assert!(sent.try_recv().is_ok());
+        assert_eq!(app.winner(), None);
+    }
+
+    #[test]
+    fn test_paused_after_history() {
+        // The engine plays White, so going back to the start leaves it to move
+        let (mut app, sent) = new_app("bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1", HumanColor::Black);
+        app.game.play("d2d3".parse().unwrap(), None, None).unwrap();
+        app.game.play("c7c6".parse().unwrap(), None, None).unwrap();
+        app.on_key(KeyCode::Home);
+        app.schedule();
+        assert!(sent.try_recv().is_err());
+        assert_eq!(app.game.line_len(), 2);
+
+        app.on_key(KeyCode::Char('m'));
+        app.schedule();
+        assert!(sent.try_recv().is_ok());
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * Drives the C API the way an embedding program would. Built and run by
+ * tests/capi.rs, exits nonzero with a message on the first failed check.
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "breakthrough.h"
+
+#define CHECK(cond)                                                       \
+    do {                                                                  \
+        if (!(cond)) {                                                    \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,        \
+                    __LINE__, #cond);                                     \
+            return 1;                                                     \
+        }                                                                 \
+    } while (0)
+
+static int test_position(void) {
+    char fen[128];
+    BtMove moves[BT_MAX_MOVES];
+    BtMove action;
+
+    BtPosition *position = bt_position_new();
+    CHECK(position != NULL);
+    CHECK(bt_position_to_play(position) == BT_WHITE);
+    CHECK(bt_position_result(position) == BT_UNDECIDED);
+    CHECK(bt_position_legal_moves(position, moves, BT_MAX_MOVES) == 22);
+    CHECK(bt_position_legal_moves(position, NULL, 0) == 22);
+
+    CHECK(!bt_move_parse("d2", &action));
+    CHECK(bt_move_parse("d2d3", &action));
+    CHECK(bt_position_make_move(position, action));
+    CHECK(bt_position_to_play(position) == BT_BLACK);
+    /* The same move again is illegal, and leaves the position as it was */
+    CHECK(!bt_position_make_move(position, action));
+    CHECK(bt_position_to_play(position) == BT_BLACK);
+
+    CHECK(bt_position_fen(position, fen, sizeof fen) == strlen(fen));
+    CHECK(strcmp(fen, "bbbbbbbb/bbbbbbbb/8/8/8/3w4/www1wwww/wwwwwwww b 1") == 0);
+    /* A short buffer gets as much as fits */
+    CHECK(bt_position_fen(position, fen, 5) > 4);
+    CHECK(strcmp(fen, "bbbb") == 0);
+
+    BtPosition *copy = bt_position_clone(position);
+    CHECK(bt_move_parse("c7c6", &action));
+    CHECK(bt_position_make_move(copy, action));
+    CHECK(bt_position_to_play(position) == BT_BLACK);
+    CHECK(bt_position_to_play(copy) == BT_WHITE);
+    bt_position_free(copy);
+    bt_position_free(position);
+    bt_position_free(NULL);
+
+    CHECK(bt_position_from_fen("not a position") == NULL);
+    position = bt_position_from_fen("w7/8/8/8/8/8/7b/8 b 2");
+    CHECK(position != NULL);
+    CHECK(bt_position_result(position) == BT_WHITE_WINS);
+    CHECK(bt_position_legal_moves(position, moves, BT_MAX_MOVES) == 0);
+    bt_position_free(position);
+
+    /* A FEN can give both sides far more pieces than a game ever has */
+    position = bt_position_from_fen("8/wwwwwwww/8/wwwwwwww/8/wwwwwwww/8/8 w 1");
+    CHECK(position != NULL);
+    CHECK(bt_position_legal_moves(position, moves, BT_MAX_MOVES) > 48);
+    bt_position_free(position);
+    return 0;
+}
+
+static int test_search(void) {
+    char text[5];
+    BtSearchResult result;
+    BtLimits limits = {4, 0};
+
+    BtSearcher *searcher = bt_searcher_new(1 << 16, NULL);
+    CHECK(searcher != NULL);
+    CHECK(bt_searcher_new(1 << 16, "/no/such/params") == NULL);
+
+    BtPosition *position = bt_position_new();
+    CHECK(bt_searcher_search(searcher, position, limits, &result));
+    CHECK(result.depth == 4);
+    CHECK(result.eval.kind == BT_HEURISTIC);
+    CHECK(result.pv_len >= 1 && result.pv_len <= 4);
+    CHECK(result.pv[0].from == result.best.from && result.pv[0].to == result.best.to);
+    CHECK(bt_move_to_string(result.best, text, sizeof text) == 4);
+    CHECK(strlen(text) == 4);
+    {
+        /* Squares off the board give an empty string */
+        BtMove off_board = {200, 1};
+        CHECK(bt_move_to_string(off_board, text, sizeof text) == 0);
+        CHECK(text[0] == '\0');
+    }
+    /* The whole line can be played */
+    for (uint32_t i = 0; i < result.pv_len; i++) {
+        CHECK(bt_position_make_move(position, result.pv[i]));
+    }
+    bt_position_free(position);
+
+    /* Both sides are a move from winning, and Black is to play */
+    position = bt_position_from_fen("8/w7/8/8/8/8/b7/8 b 1");
+    CHECK(bt_searcher_search(searcher, position, limits, &result));
+    CHECK(result.eval.kind == BT_BLACK_WIN_PLY);
+    CHECK(bt_position_make_move(position, result.best));
+    CHECK(bt_position_result(position) == BT_BLACK_WINS);
+    CHECK(!bt_searcher_search(searcher, position, limits, &result));
+    bt_position_free(position);
+
+    /* White has no pieces left, so there's nothing to search */
+    position = bt_position_from_fen("b7/8/8/8/8/8/8/8 w 1");
+    CHECK(position != NULL);
+    CHECK(bt_position_result(position) == BT_UNDECIDED);
+    CHECK(!bt_searcher_search(searcher, position, limits, &result));
+    bt_position_free(position);
+
+    /* No depth limit, stopped by the clock */
+    limits.depth = 0;
+    limits.time_ms = 50;
+    position = bt_position_new();
+    CHECK(bt_searcher_search(searcher, position, limits, &result));
+    CHECK(result.depth >= 1 && result.depth < 64);
+    bt_position_free(position);
+
+    bt_searcher_free(searcher);
+    bt_searcher_free(NULL);
+    return 0;
+}
+
+int main(void) {
+    if (test_position() != 0 || test_search() != 0) {
+        return 1;
+    }
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,66 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+/*
+ * The C API as a C program sees it: the header must match what cbindgen makes of
+ * v2/ffi.rs, and tests/c/capi_test.c is built against it and the cdylib and run.
+ */
+
+const HEADER: &str = "include/breakthrough.h";
+
+#[test]
+fn header_up_to_date() {
+    let config = cbindgen::Config::from_file("cbindgen.toml").unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(env!("CARGO_MANIFEST_DIR"))
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+    assert!(generated.contains(&format!("#define BT_MAX_PV {}\n", v2::ffi::BT_MAX_PV)));
+    assert!(generated.contains(&format!("#define BT_MAX_MOVES {}\n", v2::ffi::BT_MAX_MOVES)));
+
+    if env::var_os("UPDATE_HEADER").is_some() {
+        fs::write(HEADER, &generated).unwrap();
+    }
+    let committed = fs::read_to_string(HEADER).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "{} is out of date, regenerate it with `UPDATE_HEADER=1 cargo test --test capi`",
+        HEADER
+    );
+}
+
+#[test]
+fn c_program() {
+    // The test binary is in target/<profile>/deps, next to which cargo puts the cdylib.
+    // Building the tests doesn't build the cdylib, so it's brought up to date here.
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().and_then(|deps| deps.parent()).unwrap();
+    let mut build = Command::new(env!("CARGO"));
+    build.args(["build", "--lib"]);
+    if lib_dir.ends_with("release") {
+        build.arg("--release");
+    }
+    assert!(build.status().unwrap().success(), "The cdylib didn't build");
+    let program: PathBuf = lib_dir.join("capi_test");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .args(["-std=c99", "-Wall", "-Werror", "-Iinclude", "tests/c/capi_test.c", "-o"])
+        .arg(&program)
+        .arg(format!("-L{}", lib_dir.display()))
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lv2")
+        .status()
+        .expect("A C compiler");
+    assert!(status.success(), "capi_test.c didn't compile");
+
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "capi_test failed:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,49 @@
+use std::process::{Command, Stdio};
+
+/*
+ * The command line as a user runs it, for input that reaches the engine without
+ * going through the library's own checks
+ */
+
+fn run(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_breakthrough"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::io::Write::write_all(child.stdin.as_mut().unwrap(), input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "breakthrough {} failed:\n{}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// White has no pieces left, so the game isn't won yet but White can't move
+const STUCK: &str = "b7/8/8/8/8/8/8/8 w 1";
+
+#[test]
+fn play_without_moves() {
+    let output = run(&["play", "--strategy", "v2", "--depth", "2", "--human-color", "none", "--fen", STUCK], "");
+    assert!(output.contains("No legal moves for White"), "{}", output);
+
+    let output = run(&["play", "--strategy", "v2", "--depth", "2", "--fen", STUCK], "hint\n");
+    assert!(output.contains("No legal moves"), "{}", output);
+}
+
+#[test]
+fn play_jump_keeps_redo_line() {
+    // Back to where the engine replied to d2d3, where it waits rather than play over
+    // its reply, which can then be redone
+    let output = run(&["play", "--strategy", "v2", "--depth", "1"], "d2d3\njump 1\nfen\nd7d6\nredo\n");
+    let after_d2d3 = "bbbbbbbb/bbbbbbbb/8/8/8/3w4/www1wwww/wwwwwwww b 1";
+    // Shown after the move and the jump, then printed by fen
+    assert_eq!(output.lines().filter(|line| *line == after_d2d3).count(), 3, "{}", output);
+    assert!(output.contains("It's the engine's move, type go to let it play"), "{}", output);
+    assert!(!output.contains("Nothing to redo"), "{}", output);
+}
@@ -13,7 +13,7 @@ pub fn read_positions(
     filename: &str,
 ) -> Result<Vec<(BreakthroughNode, Evaluation, u32)>, anyhow::Error> {
     let path = Path::new(filename);
-    let file = File::open(&path)?;
+    let file = File::open(path)?;
 
     let mut nodes = Vec::new();
 
@@ -34,15 +34,8 @@ pub fn read_positions(
         };
         let mut node = BreakthroughNode::default();
         for step in moves {
-            let parsed = BreakthroughMove::from_str(step);
-            match parsed {
-                Ok(action) => {
-                    node = node.take_action(&action);
-                }
-                Err(_) => {
-                    return Err(anyhow!("Invalid move"));
-                }
-            }
+            let action = BreakthroughMove::from_str(step)?;
+            node = node.take_action(&action);
         }
 
         nodes.push((node, eval, depth));
@@ -1,6 +1,6 @@
 use v2::{
     core::{Evaluation, Player},
-    evaluation,
+    evaluation::{self, params::EvalParams},
     search::{self, table::TranspositionTable},
 };
 
@@ -9,10 +9,11 @@ mod common;
 #[test]
 fn mate_in_n_heuristic_correctness() {
     let dataset = common::read_positions("tests/data/mate-in-n.txt").unwrap();
+    let params = EvalParams::default();
     let (mut correct, mut incorrect) = (0, 0);
 
     for (node, expect_eval, _) in dataset.iter() {
-        let actual_eval = evaluation::fast_heuristic(node);
+        let actual_eval = evaluation::fast_heuristic(node, &params);
         match expect_eval {
             Evaluation::BlackWinPly(_) => {
                 if actual_eval < Evaluation::Heuristic(0) {
@@ -39,15 +40,16 @@ fn mate_in_n_heuristic_correctness() {
 #[test]
 fn mate_in_n_negamax_correctness() {
     let dataset = common::read_positions("tests/data/mate-in-n.txt").unwrap();
+    let mut params = EvalParams::default();
 
     for (node, expect_eval, depth) in dataset.iter().take(50) {
-        let eval = search::evaluate(node, *depth);
+        let eval = search::evaluate(node, *depth, &mut params).unwrap();
         let actual_eval = match node.to_play {
             Player::White => eval.1,
             Player::Black => -eval.1,
         };
         if expect_eval != &actual_eval {
-            eprintln!("{:?}\n{}\n{}", node, node.fen(), node.to_string());
+            eprintln!("{:?}\n{}\n{}", node, node.fen(), node);
         }
         assert_eq!(expect_eval, &actual_eval);
     }
@@ -56,16 +58,17 @@ fn mate_in_n_negamax_correctness() {
 #[test]
 fn mate_in_n_negamax_ttable_correctness() {
     let dataset = common::read_positions("tests/data/mate-in-n.txt").unwrap();
+    let mut params = EvalParams::default();
     let mut table = TranspositionTable::new(2usize.pow(18));
 
     for (node, expect_eval, depth) in dataset.iter().take(50) {
-        let eval = search::evaluate_with_ttable(node, *depth, &mut table);
+        let eval = search::evaluate_with_ttable(node, *depth, &mut params, &mut table).unwrap();
         let actual_eval = match node.to_play {
             Player::White => eval.1,
             Player::Black => -eval.1,
         };
         if expect_eval != &actual_eval {
-            eprintln!("{:?}\n{}\n{}", node, node.fen(), node.to_string());
+            eprintln!("{:?}\n{}\n{}", node, node.fen(), node);
         }
         assert_eq!(expect_eval, &actual_eval);
     }
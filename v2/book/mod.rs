@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io,
+    path::Path,
+    str::FromStr,
+};
+
+use rand::Rng;
+
+use crate::{
+    core::{
+        error::{position_in, ParseError, ParseErrorKind},
+        move8::BreakthroughMove,
+        node8::BreakthroughNode,
+    },
+    record::GameRecord,
+    search::zobrist::position_hash,
+};
+
+/*
+ * Opening book: weighted moves keyed by position hash.
+ *
+ * The file has one `<hash> <move> <weight>` line per book move, with the hash in hex,
+ * e.g. `0123456789abcdef d2d3 12`. Blank lines and `#` comments are skipped.
+ */
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<(BreakthroughMove, u32)>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Number of positions in the book
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn add(&mut self, node: &BreakthroughNode, action: &BreakthroughMove, weight: u32) {
+        self.add_hashed(position_hash(node), action, weight);
+    }
+
+    fn add_hashed(&mut self, hash: u64, action: &BreakthroughMove, weight: u32) {
+        let moves = self.entries.entry(hash).or_default();
+        match moves.iter_mut().find(|(m, _)| m == action) {
+            Some((_, w)) => *w = w.saturating_add(weight),
+            None => moves.push((*action, weight)),
+        }
+    }
+
+    // Book moves for `node` that are legal there, so hash collisions can't leak in
+    pub fn moves(&self, node: &BreakthroughNode) -> Vec<(BreakthroughMove, u32)> {
+        self.entries
+            .get(&position_hash(node))
+            .map(|moves| {
+                moves
+                    .iter()
+                    .filter(|(m, w)| *w > 0 && node.is_legal(m))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // The heaviest book move, ties going to the first one added
+    pub fn best(&self, node: &BreakthroughNode) -> Option<BreakthroughMove> {
+        self.moves(node)
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, w)| *w)
+            .map(|(m, _)| m)
+    }
+
+    // A book move picked with probability proportional to its weight
+    pub fn choose<R: Rng>(&self, node: &BreakthroughNode, rng: &mut R) -> Option<BreakthroughMove> {
+        let moves = self.moves(node);
+        let total: u64 = moves.iter().map(|(_, w)| *w as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = rng.gen_range(0..total);
+        for (action, weight) in moves {
+            if pick < weight as u64 {
+                return Some(action);
+            }
+            pick -= weight as u64;
+        }
+        None
+    }
+
+    // Count the moves of the first `max_plies` plies of each game. A move scores
+    // one for being played and one more if the player who made it won the game.
+    pub fn from_records(records: &[GameRecord], max_plies: usize) -> Self {
+        let mut book = Self::new();
+        for record in records {
+            let mut node = record.start.clone();
+            for recorded in record.moves.iter().take(max_plies) {
+                let won = record.result.as_ref() == Some(&node.to_play);
+                book.add(&node, &recorded.action, if won { 2 } else { 1 });
+                node = node.take_action(&recorded.action);
+            }
+        }
+        book
+    }
+
+    // Drop moves lighter than `min_weight`, and positions left without moves
+    pub fn prune(&mut self, min_weight: u32) {
+        for moves in self.entries.values_mut() {
+            moves.retain(|(_, w)| *w >= min_weight);
+        }
+        self.entries.retain(|_, moves| !moves.is_empty());
+    }
+
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for OpeningBook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Sorted so that the same book always gives the same file
+        let mut hashes: Vec<&u64> = self.entries.keys().collect();
+        hashes.sort();
+        for hash in hashes {
+            for (action, weight) in &self.entries[hash] {
+                writeln!(f, "{:016x} {} {}", hash, action, weight)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for OpeningBook {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |kind, input: &str| ParseError::new(kind, input, position_in(s, input));
+        let mut book = Self::new();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_ascii_whitespace().collect();
+            if fields.len() != 3 {
+                return Err(error(ParseErrorKind::WrongFieldCount, line));
+            }
+            let hash = u64::from_str_radix(fields[0], 16).map_err(|_| error(ParseErrorKind::InvalidNumber, fields[0]))?;
+            let action = BreakthroughMove::from_str(fields[1]).map_err(|e| e.offset(position_in(s, fields[1])))?;
+            let weight = fields[2].parse::<u32>().map_err(|_| error(ParseErrorKind::InvalidNumber, fields[2]))?;
+            book.add_hashed(hash, &action, weight);
+        }
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    use crate::{
+        core::{error::ParseErrorKind, move8::BreakthroughMove, node8::BreakthroughNode, Player},
+        record::GameRecord,
+    };
+
+    use super::OpeningBook;
+
+    fn game(moves: &[&str], winner: Player) -> GameRecord {
+        let mut record = GameRecord::default();
+        for m in moves {
+            record.push(BreakthroughMove::from_str(m).unwrap(), None, None);
+        }
+        record.result = Some(winner);
+        record
+    }
+
+    #[test]
+    fn test_book_from_records() {
+        let records = [
+            game(&["d2d3", "c7c6", "d3d4"], Player::White),
+            game(&["d2d3", "e7e6"], Player::Black),
+            game(&["a2a3", "c7c6"], Player::Black),
+        ];
+        let book = OpeningBook::from_records(&records, 2);
+        let start = BreakthroughNode::default();
+        let d2d3 = BreakthroughMove::from_str("d2d3").unwrap();
+        let a2a3 = BreakthroughMove::from_str("a2a3").unwrap();
+        assert_eq!(book.moves(&start), vec![(d2d3, 3), (a2a3, 1)]);
+        assert_eq!(book.best(&start), Some(d2d3));
+
+        // Only the first two plies are kept
+        let after = start.take_action(&d2d3);
+        assert_eq!(book.moves(&after).len(), 2);
+        let deeper = after.take_action(&BreakthroughMove::from_str("c7c6").unwrap());
+        assert_eq!(book.best(&deeper), None);
+
+        // Weighted choice only ever picks book moves, and both of them eventually
+        let mut rng = ChaCha12Rng::seed_from_u64(0);
+        let picks: Vec<BreakthroughMove> = (0..100).map(|_| book.choose(&start, &mut rng).unwrap()).collect();
+        assert!(picks.contains(&d2d3) && picks.contains(&a2a3));
+
+        let mut pruned = book.clone();
+        pruned.prune(2);
+        assert_eq!(pruned.moves(&start), vec![(d2d3, 3)]);
+        // Black's winning replies to both first moves stay
+        assert_eq!(pruned.len(), 3);
+    }
+
+    #[test]
+    fn test_book_round_trip() {
+        let records = [game(&["d2d3", "c7c6", "d3d4"], Player::White)];
+        let book = OpeningBook::from_records(&records, 10);
+        assert_eq!(book.to_string().parse::<OpeningBook>().unwrap(), book);
+        let error = |text: &str| text.parse::<OpeningBook>().unwrap_err();
+        assert_eq!(error("0123 d2d3").kind(), ParseErrorKind::WrongFieldCount);
+        assert_eq!(error("xyz d2d3 1").kind(), ParseErrorKind::InvalidNumber);
+        let e = error("# book\n0123 d2d3 1\n0123 d2d9 1");
+        assert_eq!((e.kind(), e.input(), e.position()), (ParseErrorKind::InvalidSquare, "d9", 26));
+    }
+
+    #[test]
+    fn test_book_ignores_illegal_moves() {
+        // The side to play is part of the key, and moves are checked for legality
+        let mut book = OpeningBook::new();
+        let start = BreakthroughNode::default();
+        book.add(&start, &BreakthroughMove::from_str("d7d6").unwrap(), 5);
+        assert_eq!(book.best(&start), None);
+        let mut black = start.clone();
+        black.to_play = Player::Black;
+        assert!(book.moves(&black).is_empty());
+    }
+}
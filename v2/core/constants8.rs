@@ -1,4 +1,4 @@
-/**
+/*
  * Bitboard constants for 8x8
  */
 
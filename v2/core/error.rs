@@ -0,0 +1,175 @@
+use std::{error::Error, fmt, io};
+
+/*
+ * Errors from parsing text: move notation, FEN, game records and the
+ * line-based parameter, book and training files. Also the reasons a
+ * well-formed move can be illegal.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParseErrorKind {
+    // Move notation
+    MoveLength,
+    InvalidSquare,
+    // FEN
+    MissingField,
+    TrailingField,
+    RowCount,
+    RowLength,
+    InvalidPiece,
+    InvalidPlayer,
+    InvalidMoveNumber,
+    // Game records
+    InvalidTag,
+    UnsupportedSize,
+    Unterminated,
+    UnexpectedCharacter,
+    InvalidEval,
+    IllegalMove,
+    MisplacedComment,
+    TrailingMoves,
+    PlayersDoNotAlternate,
+    // Line-based files
+    UnknownName,
+    WrongFieldCount,
+    InvalidNumber,
+}
+
+impl ParseErrorKind {
+    fn description(&self) -> &'static str {
+        match self {
+            ParseErrorKind::MoveLength => "move must be two squares, like d2d3",
+            ParseErrorKind::InvalidSquare => "square must be a column a-h and a row 1-8",
+            ParseErrorKind::MissingField => "missing field or value",
+            ParseErrorKind::TrailingField => "unexpected trailing field",
+            ParseErrorKind::RowCount => "board must have 8 rows",
+            ParseErrorKind::RowLength => "row must have 8 squares",
+            ParseErrorKind::InvalidPiece => "unexpected character in board",
+            ParseErrorKind::InvalidPlayer => "player must be w or b",
+            ParseErrorKind::InvalidMoveNumber => "move number must be a positive integer",
+            ParseErrorKind::InvalidTag => "tag must look like [Name \"value\"]",
+            ParseErrorKind::UnsupportedSize => "only 8x8 boards are supported",
+            ParseErrorKind::Unterminated => "unterminated comment or value",
+            ParseErrorKind::UnexpectedCharacter => "unexpected character",
+            ParseErrorKind::InvalidEval => "invalid eval",
+            ParseErrorKind::IllegalMove => "illegal move",
+            ParseErrorKind::MisplacedComment => "comment before the first move",
+            ParseErrorKind::TrailingMoves => "moves after the result",
+            ParseErrorKind::PlayersDoNotAlternate => "players do not alternate",
+            ParseErrorKind::UnknownName => "unknown name",
+            ParseErrorKind::WrongFieldCount => "wrong number of fields",
+            ParseErrorKind::InvalidNumber => "invalid number",
+        }
+    }
+}
+
+// The offending text and its byte position within the string being parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    input: String,
+    position: usize,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, input: &str, position: usize) -> Self {
+        Self {
+            kind,
+            input: input.to_string(),
+            position,
+        }
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // Shift the position when the parsed text was part of a larger input
+    pub fn offset(mut self, by: usize) -> Self {
+        self.position += by;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at position {}: {:?}",
+            self.kind.description(),
+            self.position,
+            self.input
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+// Loading a file can fail either way, so parse errors become invalid data
+impl From<ParseError> for io::Error {
+    fn from(error: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+// Why a move can't be played, from `BreakthroughNode::check_move`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IllegalMove {
+    GameOver,
+    NoOwnPiece,
+    WrongDirection,
+    Blocked,
+    OwnPieceOnTarget,
+    OffBoard,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IllegalMove::GameOver => "the game is already over",
+            IllegalMove::NoOwnPiece => "there is no piece of yours on the starting square",
+            IllegalMove::WrongDirection => "pieces move exactly one row forward",
+            IllegalMove::Blocked => "straight moves can't capture, the square is occupied",
+            IllegalMove::OwnPieceOnTarget => "one of your pieces is already on the target square",
+            IllegalMove::OffBoard => "the move would go off the edge of the board",
+        })
+    }
+}
+
+impl Error for IllegalMove {}
+
+// Byte position of `inner` within `outer`, which it must be a slice of
+pub(crate) fn position_in(outer: &str, inner: &str) -> usize {
+    inner.as_ptr() as usize - outer.as_ptr() as usize
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use super::{position_in, ParseError, ParseErrorKind};
+
+    #[test]
+    fn test_parse_error() {
+        let error = ParseError::new(ParseErrorKind::InvalidSquare, "d9", 2).offset(10);
+        assert_eq!(error.kind(), ParseErrorKind::InvalidSquare);
+        assert_eq!((error.input(), error.position()), ("d9", 12));
+        assert!(error.to_string().contains("at position 12: \"d9\""));
+
+        let io_error: io::Error = error.clone().into();
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidData);
+        let inner = io_error.get_ref().and_then(|e| e.downcast_ref::<ParseError>());
+        assert_eq!(inner, Some(&error));
+
+        let text = "abc def";
+        assert_eq!(position_in(text, &text[4..]), 4);
+    }
+}
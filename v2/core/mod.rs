@@ -1,10 +1,15 @@
 pub mod constants8;
+pub mod error;
 pub mod move8;
+pub mod movelist;
 pub mod node8;
+#[cfg(feature = "serde")]
+mod serialize;
 
 use std::{cmp::Ordering, ops::Neg};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
 pub enum Player {
     White,
     Black,
@@ -12,12 +17,19 @@ pub enum Player {
 
 // Note that breakthrough has no draws, so we only allow wins or undecided.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
 pub enum GameResult {
     Win(Player),
     Undecided,
 }
 
+// Serialized as e.g. {"type": "white_win_ply", "value": 3}
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum Evaluation {
     BlackWinPly(u32),
     WhiteWinPly(u32),
@@ -1,22 +1,33 @@
-use std::{str::FromStr, io::{self, ErrorKind}};
+use std::{fmt, str::FromStr};
+
+use super::error::{ParseError, ParseErrorKind};
 
 /**
  * Primitives for handling moves on an 8x8 board
  */
 
 // Player, start, end
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct BreakthroughMove(pub u8, pub u8);
 
 impl BreakthroughMove {
     fn encode_square(square: u8) -> String {
         format!("{}{}", (square % 8 + b'a') as char, 8 - square / 8)
     }
+
+    // Square index of a name like d2
+    pub fn parse_square(s: &str) -> Result<u8, ParseError> {
+        match s.as_bytes() {
+            &[col @ b'a'..=b'h', row @ b'1'..=b'8'] => Ok((b'8' - row) * 8 + (col - b'a')),
+            _ => Err(ParseError::new(ParseErrorKind::InvalidSquare, s, 0)),
+        }
+    }
 }
 
-impl ToString for BreakthroughMove {
-    fn to_string(&self) -> String {
-        format!(
+impl fmt::Display for BreakthroughMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "{}{}",
             Self::encode_square(self.0),
             Self::encode_square(self.1)
@@ -25,25 +36,14 @@ impl ToString for BreakthroughMove {
 }
 
 impl FromStr for BreakthroughMove {
-    type Err = io::Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 4 {
-            return Err(io::Error::new(ErrorKind::InvalidInput, "must be length 4"));
+        if s.len() != 4 || !s.is_ascii() {
+            return Err(ParseError::new(ParseErrorKind::MoveLength, s, 0));
         }
-        let bytes: &[u8] = s.as_bytes();
-        let start: u8 =
-            if b'a' <= bytes[0] && b'h' >= bytes[0] && b'1' <= bytes[1] && b'8' >= bytes[1] {
-                (b'8' - bytes[1]) * 8 + (bytes[0] - b'a')
-            } else {
-                return Err(io::Error::new(ErrorKind::InvalidInput, "first square is invalid"));
-            };
-        let end: u8 =
-            if b'a' <= bytes[2] && b'h' >= bytes[2] && b'1' <= bytes[3] && b'8' >= bytes[3] {
-                (b'8' - bytes[3]) * 8 + (bytes[2] - b'a')
-            } else {
-                return Err(io::Error::new(ErrorKind::InvalidInput, "second square is invalid"));
-            };
+        let start = Self::parse_square(&s[..2])?;
+        let end = Self::parse_square(&s[2..]).map_err(|e| e.offset(2))?;
         Ok(BreakthroughMove(start, end))
     }
 }
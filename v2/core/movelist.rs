@@ -0,0 +1,67 @@
+use std::{fmt, ops::Deref, slice};
+
+use super::move8::BreakthroughMove;
+
+/*
+ * Fixed-capacity move list kept on the stack, so generating moves never allocates.
+ * Every piece has at most three moves, which bounds the list by 3 * 64.
+ */
+
+pub const MAX_MOVES: usize = 3 * 64;
+
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [BreakthroughMove; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        Self {
+            moves: [BreakthroughMove(0, 0); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, action: BreakthroughMove) {
+        self.moves[self.len] = action;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn as_slice(&self) -> &[BreakthroughMove] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [BreakthroughMove];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a BreakthroughMove;
+    type IntoIter = slice::Iter<'a, BreakthroughMove>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
@@ -1,8 +1,14 @@
-use super::{constants8::*, move8::BreakthroughMove, GameResult, Player};
+use super::{
+    constants8::*,
+    error::{position_in, IllegalMove, ParseError, ParseErrorKind},
+    move8::BreakthroughMove,
+    movelist::MoveList,
+    GameResult, Player,
+};
 /**
  * 8x8-specific bitboards
  */
-use std::hash::Hash;
+use std::{fmt, hash::Hash};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BreakthroughNode {
@@ -13,59 +19,73 @@ pub struct BreakthroughNode {
 }
 
 impl BreakthroughNode {
-    fn get_moves_white(&self) -> Vec<BreakthroughMove> {
+    // Append the moves from a square in `sources` to a square in `targets`.
+    // Each direction is one shift of the bitboard, and the moves are read off the
+    // resulting targets with `trailing_zeros`.
+    pub fn generate_moves(&self, list: &mut MoveList, sources: u64, targets: u64) {
         let nonempty_squares = self.bitboard_black | self.bitboard_white;
-        // Straight-line: shift by 8 and filter to only empty destinations
-        let straight_line = (self.bitboard_white >> 8) & (!nonempty_squares);
-        // Diagonal right: filter out right column, shift by 7, filter friendly
-        let diag_right = ((self.bitboard_white & !EDGE_RIGHT) >> 7) & !self.bitboard_white;
-        // Diagonal left: filter out left column, shift by 9, filter friendly
-        let diag_left = ((self.bitboard_white & !EDGE_LEFT) >> 9) & !self.bitboard_white;
-        // Collect moves by destination
-        let mut moves = Vec::with_capacity(32);
-        for i in 0..64 {
-            if straight_line & (1 << i) > 0 {
-                moves.push(BreakthroughMove(i + 8, i))
-            }
-            if diag_right & (1 << i) > 0 {
-                moves.push(BreakthroughMove(i + 7, i))
+        match self.to_play {
+            Player::White => {
+                let own = self.bitboard_white & sources;
+                let allowed = !self.bitboard_white & targets;
+                // Straight-line: shift by 8 and filter to only empty destinations
+                push_targets(list, (own >> 8) & !nonempty_squares & targets, 8);
+                // Diagonal right: filter out right column, shift by 7, filter friendly
+                push_targets(list, ((own & !EDGE_RIGHT) >> 7) & allowed, 7);
+                // Diagonal left: filter out left column, shift by 9, filter friendly
+                push_targets(list, ((own & !EDGE_LEFT) >> 9) & allowed, 9);
             }
-            if diag_left & (1 << i) > 0 {
-                moves.push(BreakthroughMove(i + 9, i))
+            Player::Black => {
+                let own = self.bitboard_black & sources;
+                let allowed = !self.bitboard_black & targets;
+                push_targets(list, (own << 8) & !nonempty_squares & targets, -8);
+                push_targets(list, ((own & !EDGE_RIGHT) << 9) & allowed, -9);
+                push_targets(list, ((own & !EDGE_LEFT) << 7) & allowed, -7);
             }
         }
-        moves
     }
 
-    fn get_moves_black(&self) -> Vec<BreakthroughMove> {
-        let nonempty_squares = self.bitboard_black | self.bitboard_white;
-        // Straight-line: shift by 8 and filter to only empty destinations
-        let straight_line = (self.bitboard_black << 8) & (!nonempty_squares);
-        // Diagonal right: filter the right column, shift by 9, filter friendly
-        let diag_right = ((self.bitboard_black & !EDGE_RIGHT) << 9) & !self.bitboard_black;
-        // Diagonal left: filter out left column, shift by 7, filter friendly
-        let diag_left = ((self.bitboard_black & !EDGE_LEFT) << 7) & !self.bitboard_black;
-        // Collect moves by destination
-        let mut moves = Vec::with_capacity(32);
-        for i in 0..64 {
-            if straight_line & (1 << i) > 0 {
-                moves.push(BreakthroughMove(i - 8, i))
-            }
-            if diag_right & (1 << i) > 0 {
-                moves.push(BreakthroughMove(i - 9, i))
-            }
-            if diag_left & (1 << i) > 0 {
-                moves.push(BreakthroughMove(i - 7, i))
-            }
+    pub fn moves(&self) -> MoveList {
+        let mut list = MoveList::new();
+        self.generate_moves(&mut list, !0, !0);
+        list
+    }
+
+    // Moves to `targets` in the order the search should try them: moves into the
+    // opponent's two home rows (winning ones first), captures, moves into the
+    // opponent's half, other moves, and last moves off our own back row
+    pub fn staged_moves(&self, targets: u64) -> MoveList {
+        let (own, opponent, win_row, opp_start, opp_side, home_row) = match self.to_play {
+            Player::White => (
+                self.bitboard_white,
+                self.bitboard_black,
+                BLACK_FIRST_ROW,
+                BLACK_START,
+                BLACK_START | (BLACK_START << 16),
+                WHITE_FIRST_ROW,
+            ),
+            Player::Black => (
+                self.bitboard_black,
+                self.bitboard_white,
+                WHITE_FIRST_ROW,
+                WHITE_START,
+                WHITE_START | (WHITE_START >> 16),
+                BLACK_FIRST_ROW,
+            ),
+        };
+        let mut list = MoveList::new();
+        let mut remaining = targets & !own;
+        for stage in [win_row, opp_start, opponent, opp_side] {
+            self.generate_moves(&mut list, !0, remaining & stage);
+            remaining &= !stage;
         }
-        moves
+        self.generate_moves(&mut list, !home_row, remaining);
+        self.generate_moves(&mut list, home_row, remaining);
+        list
     }
 
     pub fn get_possible_actions(&self) -> Vec<BreakthroughMove> {
-        match self.to_play {
-            Player::White => self.get_moves_white(),
-            Player::Black => self.get_moves_black(),
-        }
+        self.moves().to_vec()
     }
 
     pub fn get_result(&self) -> GameResult {
@@ -103,6 +123,44 @@ impl BreakthroughNode {
         }
     }
 
+    // Check a move without generating all of them, and say why it's illegal if it is
+    pub fn check_move(&self, action: &BreakthroughMove) -> Result<(), IllegalMove> {
+        if self.is_terminal() {
+            return Err(IllegalMove::GameOver);
+        }
+        if action.0 >= 64 || action.1 >= 64 {
+            return Err(IllegalMove::OffBoard);
+        }
+        let (own, other, forward) = match self.to_play {
+            Player::White => (self.bitboard_white, self.bitboard_black, -1),
+            Player::Black => (self.bitboard_black, self.bitboard_white, 1),
+        };
+        let (start, end) = (1u64 << action.0, 1u64 << action.1);
+        if own & start == 0 {
+            return Err(IllegalMove::NoOwnPiece);
+        }
+        let (from_row, from_col) = ((action.0 / 8) as i32, (action.0 % 8) as i32);
+        let (to_row, to_col) = ((action.1 / 8) as i32, (action.1 % 8) as i32);
+        if to_row - from_row != forward {
+            return Err(IllegalMove::WrongDirection);
+        }
+        // A one row step whose index wrapped around to the other edge
+        if (to_col - from_col).abs() > 1 {
+            return Err(IllegalMove::OffBoard);
+        }
+        if own & end != 0 {
+            return Err(IllegalMove::OwnPieceOnTarget);
+        }
+        if to_col == from_col && other & end != 0 {
+            return Err(IllegalMove::Blocked);
+        }
+        Ok(())
+    }
+
+    pub fn is_legal(&self, action: &BreakthroughMove) -> bool {
+        self.check_move(action).is_ok()
+    }
+
     fn fen_row(&self, row: i32) -> String {
         let mut empty_count = 0;
         let mut output = String::new();
@@ -145,6 +203,73 @@ impl BreakthroughNode {
         output.push_str(&((self.ply + 2) / 2).to_string());
         output
     }
+
+    // Inverse of `fen`, the ply is recovered from the move number and side to play
+    pub fn from_fen(fen: &str) -> Result<Self, ParseError> {
+        let error = |kind, input: &str| ParseError::new(kind, input, position_in(fen, input));
+        let mut parts = fen.split_ascii_whitespace();
+        let (board, to_play, number) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(board), Some(to_play), Some(number)) => (board, to_play, number),
+            _ => return Err(ParseError::new(ParseErrorKind::MissingField, fen, 0)),
+        };
+        if let Some(extra) = parts.next() {
+            return Err(error(ParseErrorKind::TrailingField, extra));
+        }
+
+        let (mut bitboard_white, mut bitboard_black) = (0u64, 0u64);
+        let rows: Vec<&str> = board.split('/').collect();
+        if rows.len() != 8 {
+            return Err(error(ParseErrorKind::RowCount, board));
+        }
+        for (row, text) in rows.iter().enumerate() {
+            let mut col = 0;
+            for (i, c) in text.char_indices() {
+                if col >= 8 {
+                    return Err(error(ParseErrorKind::RowLength, text));
+                }
+                match c {
+                    'w' => bitboard_white |= 1 << (row * 8 + col),
+                    'b' => bitboard_black |= 1 << (row * 8 + col),
+                    '1'..='8' => {
+                        col += c as usize - '1' as usize;
+                    }
+                    _ => return Err(error(ParseErrorKind::InvalidPiece, &text[i..i + c.len_utf8()])),
+                }
+                col += 1;
+            }
+            if col != 8 {
+                return Err(error(ParseErrorKind::RowLength, text));
+            }
+        }
+
+        let to_play = match to_play {
+            "w" => Player::White,
+            "b" => Player::Black,
+            _ => return Err(error(ParseErrorKind::InvalidPlayer, to_play)),
+        };
+        let number = match number.parse::<u32>() {
+            Ok(n) if n > 0 => n,
+            _ => return Err(error(ParseErrorKind::InvalidMoveNumber, number)),
+        };
+        let ply = (number - 1) * 2 + if to_play == Player::Black { 1 } else { 0 };
+
+        Ok(Self {
+            bitboard_black,
+            bitboard_white,
+            to_play,
+            ply,
+        })
+    }
+}
+
+// Push a move to each square of `targets`, coming from the square `shift` past it
+fn push_targets(list: &mut MoveList, targets: u64, shift: i8) {
+    let mut remaining = targets;
+    while remaining != 0 {
+        let to = remaining.trailing_zeros() as u8;
+        list.push(BreakthroughMove((to as i8 + shift) as u8, to));
+        remaining &= remaining - 1;
+    }
 }
 
 impl Default for BreakthroughNode {
@@ -165,8 +290,8 @@ impl Hash for BreakthroughNode {
     }
 }
 
-impl ToString for BreakthroughNode {
-    fn to_string(&self) -> String {
+impl fmt::Display for BreakthroughNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut result = String::new();
         for row in 0..8 {
             for col in 0..8 {
@@ -186,6 +311,123 @@ impl ToString for BreakthroughNode {
                 result.push('\n');
             }
         }
-        result
+        f.write_str(&result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{seq::SliceRandom, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+
+    use super::BreakthroughNode;
+    use crate::core::{
+        error::{IllegalMove, ParseErrorKind},
+        move8::BreakthroughMove,
+        Player,
+    };
+
+    #[test]
+    fn test_fen_round_trip() {
+        let mut node = BreakthroughNode::default();
+        assert_eq!(BreakthroughNode::from_fen(&node.fen()).unwrap(), node);
+        for _ in 0..5 {
+            let action = node.get_possible_actions()[3];
+            node = node.take_action(&action);
+            assert_eq!(BreakthroughNode::from_fen(&node.fen()).unwrap(), node);
+        }
+    }
+
+    #[test]
+    fn test_fen_invalid() {
+        let error = |fen: &str| {
+            let e = BreakthroughNode::from_fen(fen).unwrap_err();
+            (e.kind(), e.input().to_string(), e.position())
+        };
+        assert_eq!(error(""), (ParseErrorKind::MissingField, "".to_string(), 0));
+        assert_eq!(error("8/8/8/8/8/8/8 w 1").0, ParseErrorKind::RowCount);
+        assert_eq!(error("9/8/8/8/8/8/8/8 w 1"), (ParseErrorKind::InvalidPiece, "9".to_string(), 0));
+        assert_eq!(error("8/8/8/8/8/8/8/7 w 1"), (ParseErrorKind::RowLength, "7".to_string(), 14));
+        assert_eq!(error("8/8/8/8/8/8/8/8 x 1"), (ParseErrorKind::InvalidPlayer, "x".to_string(), 16));
+        assert_eq!(error("8/8/8/8/8/8/8/8 w 0").0, ParseErrorKind::InvalidMoveNumber);
+        assert_eq!(error("8/8/8/8/8/8/8/8 w 1 x").0, ParseErrorKind::TrailingField);
+    }
+
+    #[test]
+    fn test_check_move_reasons() {
+        let node = BreakthroughNode::from_fen("8/8/8/8/3b4/3w4/7w/8 w 1").unwrap();
+        let check = |m: &str| node.check_move(&m.parse::<BreakthroughMove>().unwrap());
+        assert_eq!(check("d3e4"), Ok(()));
+        assert_eq!(check("d3c4"), Ok(()));
+        assert_eq!(check("e3e4"), Err(IllegalMove::NoOwnPiece));
+        assert_eq!(check("d4d3"), Err(IllegalMove::NoOwnPiece));
+        assert_eq!(check("d3d2"), Err(IllegalMove::WrongDirection));
+        assert_eq!(check("d3d5"), Err(IllegalMove::WrongDirection));
+        assert_eq!(check("d3e3"), Err(IllegalMove::WrongDirection));
+        assert_eq!(check("d3d4"), Err(IllegalMove::Blocked));
+        assert_eq!(check("h2a3"), Err(IllegalMove::OffBoard));
+        assert_eq!(node.check_move(&BreakthroughMove(43, 64)), Err(IllegalMove::OffBoard));
+
+        let node = BreakthroughNode::from_fen("8/8/8/8/8/4w3/3w4/8 w 1").unwrap();
+        assert_eq!(node.check_move(&"d2e3".parse().unwrap()), Err(IllegalMove::OwnPieceOnTarget));
+        assert_eq!(node.check_move(&"d2d3".parse().unwrap()), Ok(()));
+
+        let over = BreakthroughNode::from_fen("3w4/8/8/8/8/8/8/b7 b 9").unwrap();
+        assert_eq!(over.check_move(&"a1a2".parse().unwrap()), Err(IllegalMove::GameOver));
+    }
+
+    #[test]
+    fn test_check_move_matches_generation() {
+        let mut rng = ChaCha12Rng::seed_from_u64(5);
+        for _ in 0..20 {
+            let mut node = BreakthroughNode::default();
+            while !node.is_terminal() {
+                let legal = node.get_possible_actions();
+                for from in 0..64 {
+                    for to in 0..64 {
+                        let action = BreakthroughMove(from, to);
+                        assert_eq!(node.is_legal(&action), legal.contains(&action), "{} {}", node.fen(), action);
+                    }
+                }
+                node = node.take_action(legal.choose(&mut rng).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_staged_moves() {
+        // White's view of the stages, Black's is the same board upside down
+        let stage = |node: &BreakthroughNode, m: &BreakthroughMove| {
+            let (from_row, to_row) = match node.to_play {
+                Player::White => (m.0 / 8, m.1 / 8),
+                Player::Black => (7 - m.0 / 8, 7 - m.1 / 8),
+            };
+            let opponent = match node.to_play {
+                Player::White => node.bitboard_black,
+                Player::Black => node.bitboard_white,
+            };
+            match to_row {
+                0 => 0,
+                1 => 1,
+                _ if opponent & (1 << m.1) != 0 => 2,
+                2..=3 => 3,
+                _ if from_row != 7 => 4,
+                _ => 5,
+            }
+        };
+        let mut rng = ChaCha12Rng::seed_from_u64(6);
+        let mut node = BreakthroughNode::default();
+        while !node.is_terminal() {
+            let staged = node.staged_moves(!0);
+            let stages: Vec<u8> = staged.iter().map(|m| stage(&node, m)).collect();
+            assert!(stages.windows(2).all(|w| w[0] <= w[1]), "{} {:?}", node.fen(), staged);
+
+            let mut all = node.get_possible_actions();
+            let mut staged = staged.to_vec();
+            all.sort_by_key(|m| (m.0, m.1));
+            staged.sort_by_key(|m| (m.0, m.1));
+            assert_eq!(all, staged);
+            node = node.take_action(all.choose(&mut rng).unwrap());
+        }
     }
 }
@@ -0,0 +1,98 @@
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{move8::BreakthroughMove, node8::BreakthroughNode};
+
+/*
+ * Serde support for the types with a text form of their own: moves are written
+ * like "d2d3" and positions as FEN. The enums in `core` derive theirs.
+ */
+
+impl Serialize for BreakthroughMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BreakthroughMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(TextVisitor("a move like \"d2d3\"", |s: &str| s.parse()))
+    }
+}
+
+impl Serialize for BreakthroughNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.fen())
+    }
+}
+
+impl<'de> Deserialize<'de> for BreakthroughNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(TextVisitor("a position as FEN", BreakthroughNode::from_fen))
+    }
+}
+
+// Reads a string with `parse`, which says what it expects in errors
+struct TextVisitor<F>(&'static str, F);
+
+impl<'de, T, E: fmt::Display, F: FnOnce(&str) -> Result<T, E>> de::Visitor<'de> for TextVisitor<F> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+
+    fn visit_str<Er: de::Error>(self, v: &str) -> Result<T, Er> {
+        (self.1)(v).map_err(Er::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, GameResult, Player};
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let action = BreakthroughMove::from_str("d2d3").unwrap();
+        assert_eq!(serde_json::to_value(action).unwrap(), json!("d2d3"));
+        assert_eq!(round_trip(&action), action);
+
+        let node = BreakthroughNode::default().take_action(&action);
+        assert_eq!(serde_json::to_value(&node).unwrap(), json!(node.fen()));
+        assert_eq!(round_trip(&node), node);
+
+        assert_eq!(serde_json::to_value(Player::Black).unwrap(), json!("black"));
+        for result in [GameResult::Win(Player::White), GameResult::Undecided] {
+            assert_eq!(round_trip(&result), result);
+        }
+        assert_eq!(serde_json::to_value(GameResult::Win(Player::White)).unwrap(), json!({ "win": "white" }));
+
+        assert_eq!(
+            serde_json::to_value(Evaluation::WhiteWinPly(3)).unwrap(),
+            json!({ "type": "white_win_ply", "value": 3 })
+        );
+        for eval in [Evaluation::Heuristic(-40), Evaluation::WhiteWinPly(3), Evaluation::BlackWinPly(8)] {
+            assert_eq!(round_trip(&eval), eval);
+        }
+    }
+
+    #[test]
+    fn test_serde_rejects_bad_text() {
+        assert!(serde_json::from_value::<BreakthroughMove>(json!("d2")).is_err());
+        assert!(serde_json::from_value::<BreakthroughMove>(json!(12)).is_err());
+        let error = serde_json::from_value::<BreakthroughNode>(json!("8/8 w 1")).unwrap_err();
+        assert!(error.to_string().contains("row"), "{}", error);
+    }
+}
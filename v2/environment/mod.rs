@@ -0,0 +1,385 @@
+use std::{error::Error, fmt};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Player},
+    evaluation::Evaluator,
+    search::searcher::{SearchLimits, Searcher},
+    tuning::datagen::random_opening,
+};
+
+/*
+ * A reinforcement learning environment, with the action space and board layout
+ * OpenSpiel uses for Breakthrough so agents and tooling carry over. An action is
+ *
+ *   ((row * 8 + col) * 6 + direction) * 2 + capture
+ *
+ * for the piece on (row, col), row 0 being rank 8. Directions 0-2 go down the board
+ * (Black's moves) and 3-5 go up (White's), each to the left, straight and right.
+ * `capture` is 1 when the target holds an opponent's piece. Unlike OpenSpiel, White
+ * moves first, as everywhere else in this crate.
+ *
+ * Observations are three 8x8 planes, row-major with rank 8 first: the pieces of the
+ * side to move, the opponent's pieces, and a plane of ones when White is to move.
+ *
+ * Without an opponent both sides are played through `step`. With one, the engine
+ * replies to every move, and the agent plays a side picked by the seed on reset.
+ */
+
+pub const NUM_ACTIONS: usize = 64 * 6 * 2;
+pub const OBSERVATION_PLANES: usize = 3;
+pub const OBSERVATION_SIZE: usize = OBSERVATION_PLANES * 64;
+
+const DIRECTION_ROWS: [i32; 6] = [1, 1, 1, -1, -1, -1];
+const DIRECTION_COLS: [i32; 6] = [-1, 0, 1, -1, 0, 1];
+
+// The index of a move in the action space, which depends on whether it captures.
+// None if a square is off the board or the move isn't to a neighbouring square.
+pub fn action_index(node: &BreakthroughNode, action: &BreakthroughMove) -> Option<usize> {
+    if action.0 >= 64 || action.1 >= 64 {
+        return None;
+    }
+    let (from, to) = (action.0 as i32, action.1 as i32);
+    let (rows, cols) = (to / 8 - from / 8, to % 8 - from % 8);
+    let direction = (0..6).find(|&d| DIRECTION_ROWS[d] == rows && DIRECTION_COLS[d] == cols)?;
+    let opponent = match node.to_play {
+        Player::White => node.bitboard_black,
+        Player::Black => node.bitboard_white,
+    };
+    let capture = (opponent >> action.1) & 1;
+    Some((action.0 as usize * 6 + direction) * 2 + capture as usize)
+}
+
+// The move an action stands for, None if it's out of range or goes off the board.
+// The capture flag isn't checked here.
+pub fn action_move(index: usize) -> Option<BreakthroughMove> {
+    if index >= NUM_ACTIONS {
+        return None;
+    }
+    let (square, direction) = (index / 12, index / 2 % 6);
+    let row = (square / 8) as i32 + DIRECTION_ROWS[direction];
+    let col = (square % 8) as i32 + DIRECTION_COLS[direction];
+    match (0..8).contains(&row) && (0..8).contains(&col) {
+        true => Some(BreakthroughMove(square as u8, (row * 8 + col) as u8)),
+        false => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepError {
+    // Not an action in the action space
+    OutOfRange(usize),
+    // Can't be played in the current position, including a wrong capture flag
+    Illegal(usize),
+    GameOver,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::OutOfRange(index) => write!(f, "action {} is outside the action space", index),
+            StepError::Illegal(index) => write!(f, "action {} isn't legal in this position", index),
+            StepError::GameOver => f.write_str("the game is over, reset to start another"),
+        }
+    }
+}
+
+impl Error for StepError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    // For the player who took the action: 1 for a win, -1 for a loss, otherwise 0
+    pub reward: f32,
+    pub done: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    // Uniformly random moves played from the start on each reset
+    pub random_plies: u32,
+    // Side played against the engine, picked from the seed if None
+    pub agent: Option<Player>,
+    // How long the engine thinks about each reply
+    pub limits: SearchLimits,
+    // Transposition table entries for the engine, cleared on reset
+    pub table_size: usize,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            random_plies: 0,
+            agent: None,
+            limits: SearchLimits { depth: 2, time: None },
+            table_size: 2usize.pow(16),
+        }
+    }
+}
+
+struct Opponent {
+    evaluator: Box<dyn Evaluator + Send>,
+    searcher: Searcher,
+}
+
+pub struct BreakthroughEnv {
+    config: EnvConfig,
+    node: BreakthroughNode,
+    opponent: Option<Opponent>,
+    agent: Player,
+    done: bool,
+}
+
+impl BreakthroughEnv {
+    // Both sides are played through `step` unless there's an engine opponent
+    pub fn new(config: EnvConfig, opponent: Option<Box<dyn Evaluator + Send>>) -> Self {
+        let opponent = opponent.map(|evaluator| Opponent {
+            evaluator,
+            searcher: Searcher::new(config.table_size),
+        });
+        Self {
+            node: BreakthroughNode::default(),
+            agent: Player::White,
+            done: false,
+            opponent,
+            config,
+        }
+    }
+
+    // Start a new game, returning the first observation for the agent
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        self.node = random_opening(self.config.random_plies, &mut rng);
+        self.agent = match self.config.agent.clone() {
+            Some(player) => player,
+            None if rng.gen() => Player::White,
+            None => Player::Black,
+        };
+        if let Some(opponent) = &mut self.opponent {
+            opponent.searcher = Searcher::new(self.config.table_size);
+            if !self.finished() && self.node.to_play != self.agent {
+                self.reply();
+            }
+        }
+        self.done = self.finished();
+        self.observation()
+    }
+
+    // Play an action for the side to move, then the engine's reply if there's an opponent
+    pub fn step(&mut self, index: usize) -> Result<Step, StepError> {
+        if self.done {
+            return Err(StepError::GameOver);
+        }
+        if index >= NUM_ACTIONS {
+            return Err(StepError::OutOfRange(index));
+        }
+        let action = action_move(index).ok_or(StepError::Illegal(index))?;
+        if self.node.check_move(&action).is_err() || action_index(&self.node, &action) != Some(index) {
+            return Err(StepError::Illegal(index));
+        }
+        self.node = self.node.take_action(&action);
+
+        let mut reward = 0.0;
+        if self.finished() {
+            reward = 1.0;
+        } else if self.opponent.is_some() {
+            self.reply();
+            if self.finished() {
+                reward = -1.0;
+            }
+        }
+        self.done = self.finished();
+        Ok(Step {
+            observation: self.observation(),
+            reward,
+            done: self.done,
+        })
+    }
+
+    fn reply(&mut self) {
+        let opponent = self.opponent.as_mut().expect("An engine opponent");
+        let info = opponent.searcher.search(&self.node, self.config.limits, &mut *opponent.evaluator, None, |_| {});
+        if let Some(info) = info {
+            self.node = self.node.take_action(&info.best);
+        }
+    }
+
+    // Someone reached the far row, or the side to move has no moves left and loses
+    fn finished(&self) -> bool {
+        self.node.is_terminal() || self.node.moves().is_empty()
+    }
+
+    pub fn node(&self) -> &BreakthroughNode {
+        &self.node
+    }
+
+    // The side the agent plays against an engine opponent
+    pub fn agent(&self) -> &Player {
+        &self.agent
+    }
+
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    // The winner once the game is over
+    pub fn winner(&self) -> Option<Player> {
+        if !self.done {
+            return None;
+        }
+        // Whoever is to move at the end either lost the race or has no moves
+        Some(match self.node.to_play {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+        })
+    }
+
+    pub fn legal_actions(&self) -> Vec<usize> {
+        if self.done {
+            return Vec::new();
+        }
+        let mut actions: Vec<usize> = self.node.moves().iter().filter_map(|action| action_index(&self.node, action)).collect();
+        actions.sort_unstable();
+        actions
+    }
+
+    pub fn legal_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; NUM_ACTIONS];
+        for index in self.legal_actions() {
+            mask[index] = true;
+        }
+        mask
+    }
+
+    pub fn observation(&self) -> Vec<f32> {
+        let (own, opponent) = match self.node.to_play {
+            Player::White => (self.node.bitboard_white, self.node.bitboard_black),
+            Player::Black => (self.node.bitboard_black, self.node.bitboard_white),
+        };
+        let mut observation = vec![0.0; OBSERVATION_SIZE];
+        for square in 0..64 {
+            observation[square] = ((own >> square) & 1) as f32;
+            observation[64 + square] = ((opponent >> square) & 1) as f32;
+        }
+        if self.node.to_play == Player::White {
+            observation[128..].fill(1.0);
+        }
+        observation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::{
+        core::{move8::BreakthroughMove, node8::BreakthroughNode, Player},
+        evaluation::params::EvalParams,
+    };
+
+    use super::{action_index, action_move, BreakthroughEnv, EnvConfig, StepError, NUM_ACTIONS};
+
+    fn index(env: &BreakthroughEnv, action: &str) -> usize {
+        action_index(env.node(), &BreakthroughMove::from_str(action).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_action_space() {
+        // Black's a7a6 is square 8, straight down
+        let node = BreakthroughNode::default().take_action(&BreakthroughMove::from_str("a2a3").unwrap());
+        assert_eq!(action_index(&node, &BreakthroughMove::from_str("a7a6").unwrap()), Some((8 * 6 + 1) * 2));
+        // Two rows at once, and a square off the board, aren't in the action space
+        assert_eq!(action_index(&node, &BreakthroughMove::from_str("a7a5").unwrap()), None);
+        assert_eq!(action_index(&node, &BreakthroughMove(70, 1)), None);
+        assert_eq!(action_index(&node, &BreakthroughMove(8, 200)), None);
+
+        let mut decoded = 0;
+        for index in 0..NUM_ACTIONS {
+            if let Some(action) = action_move(index) {
+                decoded += 1;
+                assert_eq!(action_move(index ^ 1), Some(action));
+            }
+        }
+        // Up or down the board, 7 rows of squares can move straight and 7 of their
+        // columns each diagonal, with either capture flag
+        assert_eq!(decoded, 2 * 2 * (7 * 8 + 2 * 7 * 7));
+        assert_eq!(action_move(NUM_ACTIONS), None);
+        // a8 can't go up the board
+        assert_eq!(action_move(3 * 2), None);
+    }
+
+    #[test]
+    fn test_step_and_masks() {
+        let mut env = BreakthroughEnv::new(EnvConfig::default(), None);
+        let observation = env.reset(0);
+        assert_eq!(observation[48..64], [1.0; 16]);
+        assert_eq!(observation[64..80], [1.0; 16]);
+        assert_eq!(observation[128..], [1.0; 64]);
+
+        let legal = env.legal_actions();
+        assert_eq!(legal.len(), 22);
+        assert_eq!(env.legal_mask().iter().filter(|&&legal| legal).count(), 22);
+        for &index in &legal {
+            let action = action_move(index).unwrap();
+            assert!(env.node().is_legal(&action));
+            assert_eq!(action_index(env.node(), &action), Some(index));
+        }
+
+        let d2d3 = index(&env, "d2d3");
+        assert_eq!(env.step(d2d3 ^ 1), Err(StepError::Illegal(d2d3 ^ 1)));
+        assert_eq!(env.step(NUM_ACTIONS), Err(StepError::OutOfRange(NUM_ACTIONS)));
+        assert_eq!(env.step(3 * 2), Err(StepError::Illegal(3 * 2)));
+        let step = env.step(d2d3).unwrap();
+        assert_eq!((step.reward, step.done), (0.0, false));
+        // Black's turn, so Black's pieces come first and the side plane is empty
+        assert_eq!(step.observation[..16], [1.0; 16]);
+        assert_eq!(step.observation[128..], [0.0; 64]);
+    }
+
+    #[test]
+    fn test_win() {
+        let mut env = BreakthroughEnv::new(EnvConfig::default(), None);
+        env.reset(0);
+        env.node = BreakthroughNode::from_fen("8/w7/8/8/8/8/7b/8 w 1").unwrap();
+        // A capture flag that doesn't match the board is illegal
+        let a7b8 = index(&env, "a7b8");
+        assert!(env.step(a7b8 + 1).is_err());
+        let step = env.step(index(&env, "a7a8")).unwrap();
+        assert_eq!((step.reward, step.done), (1.0, true));
+        assert_eq!(env.winner(), Some(Player::White));
+        assert!(env.legal_actions().is_empty());
+        assert_eq!(env.step(a7b8), Err(StepError::GameOver));
+    }
+
+    #[test]
+    fn test_against_engine() {
+        let config = EnvConfig {
+            random_plies: 4,
+            ..EnvConfig::default()
+        };
+        let mut env = BreakthroughEnv::new(config.clone(), Some(Box::new(EvalParams::default())));
+        let mut other = BreakthroughEnv::new(config, Some(Box::new(EvalParams::default())));
+        let mut agents = Vec::new();
+        for seed in 0..8 {
+            assert_eq!(env.reset(seed), other.reset(seed));
+            assert_eq!(&env.node().to_play, env.agent());
+            agents.push(env.agent().clone());
+        }
+        assert!(agents.contains(&Player::White) && agents.contains(&Player::Black));
+
+        // Always play the first legal action, the engine replies each time
+        let mut reward = 0.0;
+        while !env.done() {
+            let ply = env.node().ply;
+            let step = env.step(env.legal_actions()[0]).unwrap();
+            assert!(step.done || env.node().ply == ply + 2);
+            reward = step.reward;
+        }
+        match env.winner() {
+            Some(winner) if &winner == env.agent() => assert_eq!(reward, 1.0),
+            _ => assert_eq!(reward, -1.0),
+        }
+    }
+}
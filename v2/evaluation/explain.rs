@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::core::{node8::BreakthroughNode, Player};
+
+use super::{
+    params::EvalParams,
+    runners::{black_runners, runner_race, white_runners},
+    PieceTerms,
+};
+
+/**
+ * Breakdown of `fast_heuristic` into its terms
+ */
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Term {
+    pub name: &'static str,
+    pub white: i64,
+    pub black: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub terms: Vec<Term>,
+    // Contribution of the piece on each square, positive for White
+    pub heatmap: [i64; 64],
+}
+
+impl Explanation {
+    // Equal to the `fast_heuristic` score
+    pub fn total(&self) -> i64 {
+        self.terms.iter().map(|term| term.white - term.black).sum()
+    }
+}
+
+// Add `weight` to each square in `bitboard` and return the total added
+fn spread(heatmap: &mut [i64; 64], bitboard: u64, weight: i64) -> i64 {
+    for (square, value) in heatmap.iter_mut().enumerate() {
+        if bitboard & (1 << square) != 0 {
+            *value += weight;
+        }
+    }
+    bitboard.count_ones() as i64 * weight
+}
+
+pub fn explain(node: &BreakthroughNode, params: &EvalParams) -> Explanation {
+    let mut heatmap = [0i64; 64];
+    let mut terms = Vec::new();
+
+    for (name, weight, (white, black)) in PieceTerms::new(node).weighted(params) {
+        terms.push(Term {
+            name,
+            white: spread(&mut heatmap, white, weight),
+            black: -spread(&mut heatmap, black, -weight),
+        });
+    }
+
+    let (mut white_pst, mut black_pst) = (0, 0);
+    for (square, value) in heatmap.iter_mut().enumerate() {
+        if node.bitboard_white & (1 << square) != 0 {
+            *value += params.pst.white_value(square);
+            white_pst += params.pst.white_value(square);
+        }
+        if node.bitboard_black & (1 << square) != 0 {
+            *value -= params.pst.black_value(square);
+            black_pst += params.pst.black_value(square);
+        }
+    }
+    terms.push(Term {
+        name: "pst",
+        white: white_pst,
+        black: black_pst,
+    });
+
+    // The runner bonus goes to the winner's most advanced runner
+    let runner = match runner_race(node) {
+        Some((Player::White, _)) => {
+            heatmap[white_runners(node).trailing_zeros() as usize] += params.runner;
+            (params.runner, 0)
+        }
+        Some((Player::Black, _)) => {
+            heatmap[63 - black_runners(node).leading_zeros() as usize] -= params.runner;
+            (0, params.runner)
+        }
+        None => (0, 0),
+    };
+    terms.push(Term {
+        name: "runner",
+        white: runner.0,
+        black: runner.1,
+    });
+
+    Explanation { terms, heatmap }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<14}{:>8}{:>8}{:>8}", "term", "white", "black", "diff")?;
+        for term in &self.terms {
+            writeln!(
+                f,
+                "{:<14}{:>8}{:>8}{:>8}",
+                term.name,
+                term.white,
+                term.black,
+                term.white - term.black
+            )?;
+        }
+        writeln!(f, "{:<14}{:>24}", "total", self.total())?;
+        writeln!(f)?;
+        for row in 0..8 {
+            write!(f, "{}", 8 - row)?;
+            for col in 0..8 {
+                match self.heatmap[row * 8 + col] {
+                    0 => write!(f, "{:>7}", ".")?,
+                    value => write!(f, "{:>7}", value)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, " ")?;
+        for col in 0..8u8 {
+            write!(f, "{:>7}", (b'a' + col) as char)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{node8::BreakthroughNode, Evaluation},
+        evaluation::{fast_heuristic, params::EvalParams},
+    };
+
+    use super::explain;
+
+    #[test]
+    fn test_explain_matches_heuristic() {
+        let params = EvalParams::default();
+        for fen in [
+            "bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1",
+            "bbbbbbbb/1bbb1bbb/b2b4/8/1w6/8/1wwwwwww/wwwwwwww w 3",
+            "1bbbbbbb/8/5b2/2ww1w2/5wb1/1bww2w1/4w1w1/2w2ww1 b 27",
+            "8/8/8/7b/w7/8/8/8 w 1",
+            "8/8/8/7b/w7/8/8/8 b 1",
+        ] {
+            let node = BreakthroughNode::from_fen(fen).unwrap();
+            let explanation = explain(&node, &params);
+            assert_eq!(
+                Evaluation::Heuristic(explanation.total()),
+                fast_heuristic(&node, &params)
+            );
+            assert_eq!(explanation.heatmap.iter().sum::<i64>(), explanation.total());
+        }
+    }
+}
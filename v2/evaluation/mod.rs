@@ -1,23 +1,175 @@
-use crate::core::{node8::BreakthroughNode, Evaluation, constants8::{WHITE_START, BLACK_START}};
+use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, Player, constants8::{WHITE_START, BLACK_START, EDGE_LEFT, EDGE_RIGHT}};
+
+use self::{params::EvalParams, runners::runner_race};
+
+pub mod explain;
+pub mod network;
+pub mod params;
+pub mod pst;
+pub mod runners;
+
+// Scores leaf positions for the search, from White's perspective.
+// The search calls `push` before visiting a child and `pop` after, so stateful
+// evaluators can update incrementally along the current line.
+pub trait Evaluator {
+    fn evaluate(&mut self, node: &BreakthroughNode) -> Evaluation;
+
+    fn push(&mut self, _node: &BreakthroughNode, _action: &BreakthroughMove) {}
+
+    fn pop(&mut self) {}
+}
+
+impl Evaluator for EvalParams {
+    fn evaluate(&mut self, node: &BreakthroughNode) -> Evaluation {
+        fast_heuristic(node, self)
+    }
+}
+
+impl<E: Evaluator + ?Sized> Evaluator for Box<E> {
+    fn evaluate(&mut self, node: &BreakthroughNode) -> Evaluation {
+        (**self).evaluate(node)
+    }
+
+    fn push(&mut self, node: &BreakthroughNode, action: &BreakthroughMove) {
+        (**self).push(node, action)
+    }
+
+    fn pop(&mut self) {
+        (**self).pop()
+    }
+}
 
 const CENTER: u64 = 0x00003c3c3c3c0000;
 const WHITE_SIDE: u64 = WHITE_START | (WHITE_START >> 16);
 const BLACK_SIDE: u64 = BLACK_START | (BLACK_START >> 16);
 
-// Fast heuristic must be iteration-free and should be minimally branched.
-// i.e. limits itself to whole-board bitwise operations.
-pub fn fast_heuristic(node: &BreakthroughNode) -> Evaluation {
-    let (wpop, bpop) = (
-        node.bitboard_white.count_ones() as i64 * 1000,
-        node.bitboard_black.count_ones() as i64 * 1000,
-    );
-    let (w_center, b_center) = (
-        (node.bitboard_white & CENTER).count_ones() as i64 * 400,
-        (node.bitboard_black & CENTER).count_ones() as i64 * 400,
-    );
-    let (w_attack, b_attack) = (
-        (node.bitboard_white & BLACK_SIDE).count_ones() as i64 * 750,
-        (node.bitboard_black & WHITE_SIDE).count_ones() as i64 * 750,
-    );
-    Evaluation::Heuristic((wpop + w_center + w_attack) - (bpop + b_center + b_attack))
+// Squares attacked by white pieces, i.e. their diagonal forward captures
+pub fn white_attacks(bitboard_white: u64) -> u64 {
+    ((bitboard_white & !EDGE_RIGHT) >> 7) | ((bitboard_white & !EDGE_LEFT) >> 9)
+}
+
+// Squares attacked by black pieces, i.e. their diagonal forward captures
+pub fn black_attacks(bitboard_black: u64) -> u64 {
+    ((bitboard_black & !EDGE_RIGHT) << 9) | ((bitboard_black & !EDGE_LEFT) << 7)
+}
+
+// White pieces with a move to a square Black doesn't attack
+fn white_safe_advances(node: &BreakthroughNode, b_attacks: u64) -> u64 {
+    let empty = !(node.bitboard_white | node.bitboard_black);
+    let safe = !b_attacks;
+    let straight = (node.bitboard_white >> 8) & empty & safe;
+    let diag_right = ((node.bitboard_white & !EDGE_RIGHT) >> 7) & !node.bitboard_white & safe;
+    let diag_left = ((node.bitboard_white & !EDGE_LEFT) >> 9) & !node.bitboard_white & safe;
+    (straight << 8) | (diag_right << 7) | (diag_left << 9)
+}
+
+// Black pieces with a move to a square White doesn't attack
+fn black_safe_advances(node: &BreakthroughNode, w_attacks: u64) -> u64 {
+    let empty = !(node.bitboard_white | node.bitboard_black);
+    let safe = !w_attacks;
+    let straight = (node.bitboard_black << 8) & empty & safe;
+    let diag_right = ((node.bitboard_black & !EDGE_RIGHT) << 9) & !node.bitboard_black & safe;
+    let diag_left = ((node.bitboard_black & !EDGE_LEFT) << 7) & !node.bitboard_black & safe;
+    (straight >> 8) | (diag_right >> 9) | (diag_left >> 7)
+}
+
+// Pieces counted by each per-piece term, as (white, black) bitboards
+pub(crate) struct PieceTerms {
+    material: (u64, u64),
+    center: (u64, u64),
+    attack: (u64, u64),
+    hanging: (u64, u64),
+    defended: (u64, u64),
+    safe_advance: (u64, u64),
+}
+
+impl PieceTerms {
+    pub(crate) fn new(node: &BreakthroughNode) -> Self {
+        let (white, black) = (node.bitboard_white, node.bitboard_black);
+        let (w_attacks, b_attacks) = (white_attacks(white), black_attacks(black));
+        Self {
+            material: (white, black),
+            center: (white & CENTER, black & CENTER),
+            attack: (white & BLACK_SIDE, black & WHITE_SIDE),
+            // Attacked and not defended, only counted for the side that just moved
+            // since the side to play can still capture or save its pieces
+            hanging: match node.to_play {
+                Player::White => (0, black & w_attacks & !b_attacks),
+                Player::Black => (white & b_attacks & !w_attacks, 0),
+            },
+            defended: (white & w_attacks, black & b_attacks),
+            safe_advance: (
+                white_safe_advances(node, b_attacks),
+                black_safe_advances(node, w_attacks),
+            ),
+        }
+    }
+
+    // Name, weight and pieces of each term
+    pub(crate) fn weighted(&self, params: &EvalParams) -> [(&'static str, i64, (u64, u64)); 6] {
+        [
+            ("material", params.material, self.material),
+            ("center", params.center, self.center),
+            ("attack", params.attack, self.attack),
+            ("hanging", params.hanging, self.hanging),
+            ("defended", params.defended, self.defended),
+            ("safe_advance", params.safe_advance, self.safe_advance),
+        ]
+    }
+}
+
+// Fast heuristic should be minimally branched, i.e. limit itself to whole-board
+// bitwise operations. The one loop is the runner race's shadow fill, a fixed six
+// steps per side that doesn't depend on the position. It takes a call from about
+// 44ns to 61ns, and a depth 5 search from 7.8M to 6.6M evaluations a second.
+pub fn fast_heuristic(node: &BreakthroughNode, params: &EvalParams) -> Evaluation {
+    let mut score = 0;
+    for (_, weight, (white, black)) in PieceTerms::new(node).weighted(params) {
+        score += (white.count_ones() as i64 - black.count_ones() as i64) * weight;
+    }
+    score += params.pst.score_white(node.bitboard_white);
+    score -= params.pst.score_black(node.bitboard_black);
+    // A runner that wins the race is a near-certain win, even when the exact
+    // ply can't be proven
+    score += match runner_race(node) {
+        Some((Player::White, _)) => params.runner,
+        Some((Player::Black, _)) => -params.runner,
+        None => 0,
+    };
+    Evaluation::Heuristic(score)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::node8::BreakthroughNode;
+
+    use super::{black_attacks, black_safe_advances, white_attacks, white_safe_advances};
+
+    #[test]
+    fn test_attack_maps() {
+        // White on b2 (49) attacks a3 (40) and c3 (42), not wrapping at the edges
+        assert_eq!(white_attacks(1 << 49), (1 << 40) | (1 << 42));
+        assert_eq!(white_attacks(1 << 48), 1 << 41);
+        assert_eq!(white_attacks(1 << 55), 1 << 46);
+        // Black on b7 (9) attacks a6 (16) and c6 (18)
+        assert_eq!(black_attacks(1 << 9), (1 << 16) | (1 << 18));
+        assert_eq!(black_attacks(1 << 15), 1 << 22);
+    }
+
+    #[test]
+    fn test_safe_advances() {
+        // White d4 and black e6 each have at least one forward square the other doesn't attack
+        let node = BreakthroughNode::from_fen("8/8/4b3/8/3w4/8/8/8 w 1").unwrap();
+        let (w_attacks, b_attacks) = (
+            white_attacks(node.bitboard_white),
+            black_attacks(node.bitboard_black),
+        );
+        assert_eq!(white_safe_advances(&node, b_attacks), node.bitboard_white);
+        assert_eq!(black_safe_advances(&node, w_attacks), node.bitboard_black);
+
+        // A white piece whose three forward squares are all attacked cannot advance safely
+        let node = BreakthroughNode::from_fen("8/8/2bbb3/8/3w4/8/8/8 w 1").unwrap();
+        let b_attacks = black_attacks(node.bitboard_black);
+        assert_eq!(white_safe_advances(&node, b_attacks), 0);
+    }
 }
@@ -0,0 +1,350 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, Player};
+
+use super::Evaluator;
+
+/*
+Small fully-connected network over the two bitboards.
+
+Inputs are one feature per (color, square): square n of White is feature n and
+square n of Black is feature 64 + n. A single hidden layer with clipped ReLU
+feeds one output, scored from White's perspective like `fast_heuristic`.
+
+Everything is integer: the hidden layer is quantized by QA and the output
+weights by QB, so a score is (sum(clamp(h, 0, QA) * w) + b) * scale / (QA * QB).
+
+File format, all little-endian:
+  magic  b"BTNN"
+  u32    version (1)
+  u32    hidden size H
+  i32    output scale
+  i16    input weights, 128 * H, feature-major
+  i16    hidden biases, H
+  i16    output weights, H
+  i32    output bias
+ */
+
+pub const INPUTS: usize = 128;
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+const MAGIC: &[u8; 4] = b"BTNN";
+const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    hidden: usize,
+    scale: i32,
+    input_weights: Vec<i16>,
+    hidden_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl Network {
+    // Returns None unless the weight vectors match the hidden size
+    pub fn new(
+        scale: i32,
+        input_weights: Vec<i16>,
+        hidden_biases: Vec<i16>,
+        output_weights: Vec<i16>,
+        output_bias: i32,
+    ) -> Option<Self> {
+        let hidden = hidden_biases.len();
+        if input_weights.len() != INPUTS * hidden || output_weights.len() != hidden {
+            return None;
+        }
+        Some(Self {
+            hidden,
+            scale,
+            input_weights,
+            hidden_biases,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.hidden
+    }
+
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + (INPUTS + 2) * self.hidden * 2 + 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.hidden as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.scale.to_le_bytes());
+        for weights in [&self.input_weights, &self.hidden_biases, &self.output_weights] {
+            for w in weights.iter() {
+                bytes.extend_from_slice(&w.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, io::Error> {
+        let invalid = |msg: &str| io::Error::new(ErrorKind::InvalidData, msg.to_string());
+        let mut reader = ByteReader { bytes, offset: 0 };
+        if reader.take(4).ok_or_else(|| invalid("truncated header"))? != MAGIC {
+            return Err(invalid("not a network file"));
+        }
+        let version = reader.u32().ok_or_else(|| invalid("truncated header"))?;
+        if version != VERSION {
+            return Err(invalid("unsupported network version"));
+        }
+        let hidden = reader.u32().ok_or_else(|| invalid("truncated header"))? as usize;
+        let scale = reader.i32().ok_or_else(|| invalid("truncated header"))?;
+        let input_weights = reader.i16s(INPUTS * hidden).ok_or_else(|| invalid("truncated weights"))?;
+        let hidden_biases = reader.i16s(hidden).ok_or_else(|| invalid("truncated weights"))?;
+        let output_weights = reader.i16s(hidden).ok_or_else(|| invalid("truncated weights"))?;
+        let output_bias = reader.i32().ok_or_else(|| invalid("truncated weights"))?;
+        if reader.offset != bytes.len() {
+            return Err(invalid("trailing data after network"));
+        }
+        Self::new(scale, input_weights, hidden_biases, output_weights, output_bias)
+            .ok_or_else(|| invalid("inconsistent network sizes"))
+    }
+
+    fn feature(player: &Player, square: u8) -> usize {
+        match player {
+            Player::White => square as usize,
+            Player::Black => 64 + square as usize,
+        }
+    }
+
+    fn add_feature(&self, accumulator: &mut [i32], feature: usize) {
+        let weights = &self.input_weights[feature * self.hidden..(feature + 1) * self.hidden];
+        for (a, w) in accumulator.iter_mut().zip(weights) {
+            *a += *w as i32;
+        }
+    }
+
+    fn remove_feature(&self, accumulator: &mut [i32], feature: usize) {
+        let weights = &self.input_weights[feature * self.hidden..(feature + 1) * self.hidden];
+        for (a, w) in accumulator.iter_mut().zip(weights) {
+            *a -= *w as i32;
+        }
+    }
+
+    // Hidden layer pre-activations computed from scratch
+    fn refresh(&self, accumulator: &mut [i32], node: &BreakthroughNode) {
+        for (a, b) in accumulator.iter_mut().zip(&self.hidden_biases) {
+            *a = *b as i32;
+        }
+        for (player, bitboard) in [
+            (Player::White, node.bitboard_white),
+            (Player::Black, node.bitboard_black),
+        ] {
+            let mut remaining = bitboard;
+            while remaining != 0 {
+                let square = remaining.trailing_zeros() as u8;
+                self.add_feature(accumulator, Self::feature(&player, square));
+                remaining &= remaining - 1;
+            }
+        }
+    }
+
+    fn output(&self, accumulator: &[i32]) -> i64 {
+        let mut sum = self.output_bias as i64;
+        for (a, w) in accumulator.iter().zip(&self.output_weights) {
+            sum += (*a).clamp(0, QA) as i64 * *w as i64;
+        }
+        sum * self.scale as i64 / (QA * QB) as i64
+    }
+
+    // Score a single position without any incremental state
+    pub fn evaluate(&self, node: &BreakthroughNode) -> i64 {
+        let mut accumulator = vec![0; self.hidden];
+        self.refresh(&mut accumulator, node);
+        self.output(&accumulator)
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.offset..self.offset.checked_add(n)?)?;
+        self.offset += n;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i16s(&mut self, n: usize) -> Option<Vec<i16>> {
+        let bytes = self.take(n.checked_mul(2)?)?;
+        Some(
+            bytes
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect(),
+        )
+    }
+}
+
+// One stack entry per position along the current search line
+struct Accumulator {
+    bitboard_white: u64,
+    bitboard_black: u64,
+    values: Vec<i32>,
+}
+
+// Evaluator keeping a stack of accumulators in step with the search, so each
+// child only updates the features touched by its move
+pub struct NetworkEvaluator {
+    network: Network,
+    stack: Vec<Accumulator>,
+    len: usize,
+}
+
+impl NetworkEvaluator {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            stack: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    // Make sure the top of the stack holds `node`, recomputing it if not
+    fn sync(&mut self, node: &BreakthroughNode) {
+        if self.len == 0 {
+            self.len = 1;
+        }
+        self.reserve(self.len);
+        let top = &mut self.stack[self.len - 1];
+        if top.bitboard_white != node.bitboard_white || top.bitboard_black != node.bitboard_black {
+            self.network.refresh(&mut top.values, node);
+            top.bitboard_white = node.bitboard_white;
+            top.bitboard_black = node.bitboard_black;
+        }
+    }
+
+    // Allocate stack entries up to `len` once, they are reused afterwards
+    fn reserve(&mut self, len: usize) {
+        while self.stack.len() < len {
+            // The empty board, whose accumulator is just the biases
+            self.stack.push(Accumulator {
+                bitboard_white: 0,
+                bitboard_black: 0,
+                values: self.network.hidden_biases.iter().map(|b| *b as i32).collect(),
+            });
+        }
+    }
+}
+
+impl Evaluator for NetworkEvaluator {
+    fn evaluate(&mut self, node: &BreakthroughNode) -> Evaluation {
+        self.sync(node);
+        Evaluation::Heuristic(self.network.output(&self.stack[self.len - 1].values))
+    }
+
+    fn push(&mut self, node: &BreakthroughNode, action: &BreakthroughMove) {
+        self.sync(node);
+        self.reserve(self.len + 1);
+        let (parent, child) = self.stack.split_at_mut(self.len);
+        let (parent, child) = (&parent[self.len - 1], &mut child[0]);
+        child.values.copy_from_slice(&parent.values);
+
+        let opponent = match node.to_play {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+        };
+        let captured = match node.to_play {
+            Player::White => node.bitboard_black & (1 << action.1) != 0,
+            Player::Black => node.bitboard_white & (1 << action.1) != 0,
+        };
+        self.network.remove_feature(&mut child.values, Network::feature(&node.to_play, action.0));
+        self.network.add_feature(&mut child.values, Network::feature(&node.to_play, action.1));
+        if captured {
+            self.network.remove_feature(&mut child.values, Network::feature(&opponent, action.1));
+        }
+        let next = node.take_action(action);
+        child.bitboard_white = next.bitboard_white;
+        child.bitboard_black = next.bitboard_black;
+        self.len += 1;
+    }
+
+    fn pop(&mut self) {
+        self.len = self.len.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{seq::SliceRandom, Rng, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+
+    use crate::{
+        core::{node8::BreakthroughNode, Evaluation},
+        evaluation::Evaluator,
+    };
+
+    use super::{Network, NetworkEvaluator, INPUTS};
+
+    fn random_network(hidden: usize, seed: u64) -> Network {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let mut weights = |n: usize| (0..n).map(|_| rng.gen_range(-64..64)).collect::<Vec<i16>>();
+        let (input, biases, output) = (weights(INPUTS * hidden), weights(hidden), weights(hidden));
+        Network::new(400, input, biases, output, 17).unwrap()
+    }
+
+    #[test]
+    fn test_network_bytes_round_trip() {
+        let network = random_network(8, 1);
+        let bytes = network.to_bytes();
+        assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+        assert!(Network::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Network::from_bytes(b"nope").is_err());
+    }
+
+    #[test]
+    fn test_incremental_matches_refresh() {
+        let network = random_network(16, 2);
+        let mut evaluator = NetworkEvaluator::new(network.clone());
+        let mut rng = ChaCha12Rng::seed_from_u64(3);
+        let mut node = BreakthroughNode::default();
+        let mut depth = 0;
+        while !node.is_terminal() {
+            assert_eq!(evaluator.evaluate(&node), Evaluation::Heuristic(network.evaluate(&node)));
+            let action = *node.get_possible_actions().choose(&mut rng).unwrap();
+            evaluator.push(&node, &action);
+            node = node.take_action(&action);
+            depth += 1;
+        }
+        // Unwinding the whole line leaves the root accumulator intact
+        for _ in 0..depth {
+            evaluator.pop();
+        }
+        assert_eq!(
+            evaluator.evaluate(&BreakthroughNode::default()),
+            Evaluation::Heuristic(network.evaluate(&BreakthroughNode::default()))
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use crate::core::error::{position_in, ParseError, ParseErrorKind};
+
+use super::pst::PieceSquareTable;
+
+/**
+ * Tunable weights for the heuristic evaluation
+ */
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParams {
+    // Value of each piece on the board
+    pub material: i64,
+    // Bonus for each piece in the `CENTER` box
+    pub center: i64,
+    // Bonus for each piece on the opponent's side of the board
+    pub attack: i64,
+    // Bonus for each piece attacked by the opponent and not defended (usually negative)
+    pub hanging: i64,
+    // Bonus for each piece defended by a friendly piece. Off by default, since no
+    // value beat 0 in matches and positive ones lose.
+    pub defended: i64,
+    // Bonus for each piece with a move to a square the opponent doesn't attack. Also
+    // 0 by default, as 10 to 30 were level with 0 in matches; left for tuning.
+    pub safe_advance: i64,
+    // Bonus for having a runner that can't be caught and wins the race
+    pub runner: i64,
+    // Per-square values from White's perspective, mirrored for Black
+    pub pst: PieceSquareTable,
+}
+
+impl EvalParams {
+    // Name and number of values of each parameter, in file and vector order
+    pub const LAYOUT: &'static [(&'static str, usize)] = &[
+        ("material", 1),
+        ("center", 1),
+        ("attack", 1),
+        ("hanging", 1),
+        ("defended", 1),
+        ("safe_advance", 1),
+        ("runner", 1),
+        ("pst", 64),
+    ];
+
+    // Flatten the parameters into a single vector following `LAYOUT`
+    pub fn to_values(&self) -> Vec<i64> {
+        let mut values = vec![
+            self.material,
+            self.center,
+            self.attack,
+            self.hanging,
+            self.defended,
+            self.safe_advance,
+            self.runner,
+        ];
+        values.extend_from_slice(self.pst.values());
+        values
+    }
+
+    // Inverse of `to_values`, returns None if the length doesn't match `LAYOUT`
+    pub fn from_values(values: &[i64]) -> Option<Self> {
+        if values.len() != Self::len() {
+            return None;
+        }
+        Some(Self {
+            material: values[0],
+            center: values[1],
+            attack: values[2],
+            hanging: values[3],
+            defended: values[4],
+            safe_advance: values[5],
+            runner: values[6],
+            pst: PieceSquareTable::new(values[7..71].try_into().ok()?),
+        })
+    }
+
+    // Total number of values across all parameters
+    pub fn len() -> usize {
+        Self::LAYOUT.iter().map(|(_, n)| n).sum()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, io::Error> {
+        Ok(fs::read_to_string(path)?.parse()?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self {
+            material: 1000,
+            center: 400,
+            attack: 750,
+            hanging: -400,
+            defended: 0,
+            safe_advance: 0,
+            runner: 8000,
+            pst: PieceSquareTable::default(),
+        }
+    }
+}
+
+// One `name value...` line per parameter, in `LAYOUT` order
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values = self.to_values();
+        let mut offset = 0;
+        for (name, count) in Self::LAYOUT {
+            write!(f, "{}", name)?;
+            for value in &values[offset..offset + count] {
+                write!(f, " {}", value)?;
+            }
+            writeln!(f)?;
+            offset += count;
+        }
+        Ok(())
+    }
+}
+
+// Parameters missing from the input keep their default value, so files written
+// before a parameter was added still load. Blank lines and `#` comments are skipped.
+impl FromStr for EvalParams {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |kind, input: &str| ParseError::new(kind, input, position_in(s, input));
+        let mut values = Self::default().to_values();
+        for line in s.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.split_ascii_whitespace();
+            let name = parts.next().unwrap_or("");
+            let mut offset = 0;
+            let mut found = None;
+            for (param, count) in Self::LAYOUT {
+                if *param == name {
+                    found = Some(*count);
+                    break;
+                }
+                offset += count;
+            }
+            let count = match found {
+                Some(count) => count,
+                None => return Err(error(ParseErrorKind::UnknownName, name)),
+            };
+            let parsed = parts
+                .map(|v| v.parse::<i64>().map_err(|_| error(ParseErrorKind::InvalidNumber, v)))
+                .collect::<Result<Vec<i64>, _>>()?;
+            if parsed.len() != count {
+                return Err(error(ParseErrorKind::WrongFieldCount, line));
+            }
+            values[offset..offset + count].copy_from_slice(&parsed);
+        }
+        Ok(Self::from_values(&values).expect("values should match layout"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EvalParams;
+    use crate::core::error::ParseErrorKind;
+    use crate::evaluation::pst::PieceSquareTable;
+
+    #[test]
+    fn test_params_round_trip() {
+        let mut pst = [0i64; 64];
+        pst[12] = -7;
+        pst[63] = 40;
+        let params = EvalParams {
+            material: 900,
+            center: -20,
+            attack: 1,
+            hanging: -3,
+            defended: 4,
+            safe_advance: 5,
+            runner: 6,
+            pst: PieceSquareTable::new(pst),
+        };
+        assert_eq!(params.to_string().parse::<EvalParams>().unwrap(), params);
+        assert_eq!(EvalParams::from_values(&params.to_values()), Some(params));
+    }
+
+    #[test]
+    fn test_params_partial_file() {
+        let params: EvalParams = "# only material\nmaterial 5\n".parse().unwrap();
+        assert_eq!(params.material, 5);
+        assert_eq!(params.center, EvalParams::default().center);
+        let error = |text: &str| text.parse::<EvalParams>().unwrap_err();
+        assert_eq!(error("bogus 1").kind(), ParseErrorKind::UnknownName);
+        assert_eq!(error("center 1 2").kind(), ParseErrorKind::WrongFieldCount);
+        let e = error("material 5\ncenter x");
+        assert_eq!((e.kind(), e.input(), e.position()), (ParseErrorKind::InvalidNumber, "x", 18));
+    }
+}
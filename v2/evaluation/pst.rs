@@ -0,0 +1,133 @@
+/*
+ * Bitboard-based piece-square tables
+ */
+
+// Enough bit planes for any table whose values span less than 2^32
+const MAX_PLANES: usize = 32;
+
+// A per-square table written from White's perspective (square numbering as in
+// `constants8`). Black uses the same table mirrored top-to-bottom.
+//
+// The table is stored as bit planes: plane k holds the squares whose value
+// (relative to the smallest entry) has bit k set. Scoring a bitboard then takes
+// one popcount per plane instead of a loop over pieces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PieceSquareTable {
+    values: [i64; 64],
+    offset: i64,
+    planes: usize,
+    white_planes: [u64; MAX_PLANES],
+    black_planes: [u64; MAX_PLANES],
+}
+
+impl PieceSquareTable {
+    pub fn new(values: [i64; 64]) -> Self {
+        let offset = values.iter().copied().min().unwrap_or(0);
+        let range = values.iter().map(|v| v - offset).max().unwrap_or(0) as u64;
+        let planes = (64 - range.leading_zeros() as usize).min(MAX_PLANES);
+
+        let mut white_planes = [0u64; MAX_PLANES];
+        let mut black_planes = [0u64; MAX_PLANES];
+        for (k, plane) in white_planes.iter_mut().enumerate().take(planes) {
+            for (square, value) in values.iter().enumerate() {
+                if (value - offset) & (1 << k) != 0 {
+                    *plane |= 1 << square;
+                }
+            }
+        }
+        // Rows are bytes, so reversing the bytes mirrors the board vertically
+        for (black, white) in black_planes.iter_mut().zip(white_planes).take(planes) {
+            *black = white.swap_bytes();
+        }
+
+        Self {
+            values,
+            offset,
+            planes,
+            white_planes,
+            black_planes,
+        }
+    }
+
+    pub fn values(&self) -> &[i64; 64] {
+        &self.values
+    }
+
+    // Value of a square from White's perspective
+    pub fn white_value(&self, square: usize) -> i64 {
+        self.values[square]
+    }
+
+    // Value of a square from Black's perspective
+    pub fn black_value(&self, square: usize) -> i64 {
+        self.values[square ^ 56]
+    }
+
+    // Sum of the table over the white pieces
+    pub fn score_white(&self, bitboard: u64) -> i64 {
+        Self::score(bitboard, &self.white_planes[..self.planes], self.offset)
+    }
+
+    // Sum of the mirrored table over the black pieces
+    pub fn score_black(&self, bitboard: u64) -> i64 {
+        Self::score(bitboard, &self.black_planes[..self.planes], self.offset)
+    }
+
+    fn score(bitboard: u64, planes: &[u64], offset: i64) -> i64 {
+        let mut total = bitboard.count_ones() as i64 * offset;
+        for (k, plane) in planes.iter().enumerate() {
+            total += ((bitboard & plane).count_ones() as i64) << k;
+        }
+        total
+    }
+}
+
+impl Default for PieceSquareTable {
+    // Rows from White's goal (row 0) to White's home row (row 7). Advancing is
+    // worth more the closer a piece gets, and the home row holds off runners.
+    fn default() -> Self {
+        const ROWS: [i64; 8] = [0, 250, 120, 60, 25, 10, 0, 40];
+        const EDGE: i64 = -30;
+        let mut values = [0i64; 64];
+        for (square, value) in values.iter_mut().enumerate() {
+            let (row, col) = (square / 8, square % 8);
+            *value = ROWS[row];
+            if col == 0 || col == 7 {
+                *value += EDGE;
+            }
+        }
+        Self::new(values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PieceSquareTable;
+
+    #[test]
+    fn test_score_matches_table() {
+        let mut values = [0i64; 64];
+        for (square, value) in values.iter_mut().enumerate() {
+            *value = (square as i64 * 37) % 101 - 50;
+        }
+        let table = PieceSquareTable::new(values);
+        let bitboard: u64 = 0x8142_2418_0f00_f0a5;
+        let (mut white, mut black) = (0, 0);
+        for square in 0..64 {
+            if bitboard & (1 << square) != 0 {
+                white += table.white_value(square);
+                black += table.black_value(square);
+            }
+        }
+        assert_eq!(table.score_white(bitboard), white);
+        assert_eq!(table.score_black(bitboard), black);
+    }
+
+    #[test]
+    fn test_black_mirrors_white() {
+        let table = PieceSquareTable::default();
+        // A white piece on a7 is as advanced as a black piece on a2
+        assert_eq!(table.score_white(1 << 8), table.score_black(1 << 48));
+        assert_eq!(table.score_white(0xff << 56), table.score_black(0xff));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::core::{
+    constants8::{EDGE_LEFT, EDGE_RIGHT},
+    node8::BreakthroughNode,
+    Evaluation, Player,
+};
+
+/*
+ * Unstoppable runner detection
+ *
+ * Pieces only move forward, one row per move and at most one column sideways.
+ * For an enemy piece to capture a runner it has to reach a square diagonally in
+ * front of it, so a white piece on (r, c) can only ever be caught by a black
+ * piece on (rb, cb) with rb < r and |cb - c| <= r - rb, i.e. one inside the cone
+ * widening by a column per row in front of it. The cone also covers every
+ * square that could block its straight move, and diagonal moves can't be
+ * blocked since they capture. A piece with an empty cone reaches the last row.
+ */
+
+// Every square within some black piece's backward-widening reach
+fn black_shadow(bitboard_black: u64) -> u64 {
+    let spread = |x: u64| (x << 8) | ((x & !EDGE_RIGHT) << 9) | ((x & !EDGE_LEFT) << 7);
+    let mut shadow = spread(bitboard_black);
+    for _ in 0..6 {
+        shadow |= spread(shadow);
+    }
+    shadow
+}
+
+// Every square within some white piece's backward-widening reach
+fn white_shadow(bitboard_white: u64) -> u64 {
+    let spread = |x: u64| (x >> 8) | ((x & !EDGE_RIGHT) >> 7) | ((x & !EDGE_LEFT) >> 9);
+    let mut shadow = spread(bitboard_white);
+    for _ in 0..6 {
+        shadow |= spread(shadow);
+    }
+    shadow
+}
+
+// White pieces that no black piece can catch
+pub fn white_runners(node: &BreakthroughNode) -> u64 {
+    node.bitboard_white & !black_shadow(node.bitboard_black)
+}
+
+// Black pieces that no white piece can catch
+pub fn black_runners(node: &BreakthroughNode) -> u64 {
+    node.bitboard_black & !white_shadow(node.bitboard_white)
+}
+
+// Moves White needs to reach the last row with the most advanced piece of a set
+fn white_distance(bitboard: u64) -> Option<u32> {
+    match bitboard {
+        0 => None,
+        _ => Some(bitboard.trailing_zeros() / 8),
+    }
+}
+
+// Moves Black needs to reach the last row with the most advanced piece of a set
+fn black_distance(bitboard: u64) -> Option<u32> {
+    match bitboard {
+        0 => None,
+        _ => Some(bitboard.leading_zeros() / 8),
+    }
+}
+
+// The winner of a race between runners, if one side's runner arrives first no
+// matter what the other side does. The side to play is a tempo ahead, so it
+// wins ties.
+pub fn runner_race(node: &BreakthroughNode) -> Option<(Player, u32)> {
+    let white = white_distance(white_runners(node));
+    let black = black_distance(black_runners(node));
+    // Any piece might get through, so compare against the closest one
+    let black_any = black_distance(node.bitboard_black).unwrap_or(u32::MAX);
+    let white_any = white_distance(node.bitboard_white).unwrap_or(u32::MAX);
+    match node.to_play {
+        Player::White => match (white, black) {
+            (Some(w), _) if w <= black_any => Some((Player::White, w)),
+            (_, Some(b)) if b < white_any => Some((Player::Black, b)),
+            _ => None,
+        },
+        Player::Black => match (white, black) {
+            (_, Some(b)) if b <= white_any => Some((Player::Black, b)),
+            (Some(w), _) if w < black_any => Some((Player::White, w)),
+            _ => None,
+        },
+    }
+}
+
+// A proven result when a runner wins the race and no other piece of the
+// winner could get there sooner, so the winning ply is exact.
+pub fn runner_win(node: &BreakthroughNode) -> Option<Evaluation> {
+    let (winner, distance) = runner_race(node)?;
+    // Moves are made at plies ply, ply + 2, ... by the side to play
+    let tempo = if winner == node.to_play { 1 } else { 0 };
+    let ply = node.ply + 2 * distance - tempo;
+    match winner {
+        Player::White if white_distance(node.bitboard_white) == Some(distance) => {
+            Some(Evaluation::WhiteWinPly(ply))
+        }
+        Player::Black if black_distance(node.bitboard_black) == Some(distance) => {
+            Some(Evaluation::BlackWinPly(ply))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::core::{node8::BreakthroughNode, Evaluation, GameResult, Player};
+
+    use super::{black_runners, runner_race, runner_win, white_runners};
+
+    // Plain minimax without any shortcuts, only usable on nearly empty boards
+    fn solve(node: &BreakthroughNode, depth: u32) -> Evaluation {
+        match node.get_result() {
+            GameResult::Win(Player::White) => return Evaluation::WhiteWinPly(node.ply),
+            GameResult::Win(Player::Black) => return Evaluation::BlackWinPly(node.ply),
+            GameResult::Undecided if depth == 0 => return Evaluation::Heuristic(0),
+            GameResult::Undecided => {}
+        }
+        let children = node
+            .get_possible_actions()
+            .into_iter()
+            .map(|action| solve(&node.take_action(&action), depth - 1));
+        // A side without pieces left to move has lost
+        match node.to_play {
+            Player::White => children.max().unwrap_or(Evaluation::BlackWinPly(node.ply)),
+            Player::Black => children.min().unwrap_or(Evaluation::WhiteWinPly(node.ply)),
+        }
+    }
+
+    #[test]
+    fn test_runners() {
+        assert_eq!(white_runners(&BreakthroughNode::default()), 0);
+        assert_eq!(black_runners(&BreakthroughNode::default()), 0);
+
+        // White a4 and black h5 are out of each other's reach
+        let node = BreakthroughNode::from_fen("8/8/8/7b/w7/8/8/8 w 1").unwrap();
+        assert_eq!(white_runners(&node), node.bitboard_white);
+        assert_eq!(black_runners(&node), node.bitboard_black);
+
+        // Black d7 covers the whole cone in front of white d4
+        let node = BreakthroughNode::from_fen("8/3b4/8/8/3w4/8/8/8 w 1").unwrap();
+        assert_eq!(white_runners(&node), 0);
+        // Black a8 could still reach the path of a piece on e4, but not f4
+        let node = BreakthroughNode::from_fen("b7/8/8/8/4w3/8/8/8 w 1").unwrap();
+        assert_eq!(white_runners(&node), 0);
+        let node = BreakthroughNode::from_fen("b7/8/8/8/5w2/8/8/8 w 1").unwrap();
+        assert_eq!(white_runners(&node), node.bitboard_white);
+    }
+
+    #[test]
+    fn test_runner_race_tempo() {
+        // Both need four moves, the side to play gets there first
+        let node = BreakthroughNode::from_fen("8/8/8/7b/w7/8/8/8 w 1").unwrap();
+        assert_eq!(runner_race(&node), Some((Player::White, 4)));
+        assert_eq!(runner_win(&node), Some(Evaluation::WhiteWinPly(7)));
+        let node = BreakthroughNode::from_fen("8/8/8/7b/w7/8/8/8 b 1").unwrap();
+        assert_eq!(runner_race(&node), Some((Player::Black, 4)));
+        assert_eq!(runner_win(&node), Some(Evaluation::BlackWinPly(8)));
+
+        // A closer white piece that might be caught makes the ply inexact
+        let node = BreakthroughNode::from_fen("6b1/8/7w/8/w7/8/8/8 w 1").unwrap();
+        assert_eq!(runner_race(&node), Some((Player::White, 4)));
+        assert_eq!(runner_win(&node), None);
+    }
+
+    #[test]
+    fn test_runner_win_matches_minimax() {
+        for fen in [
+            "8/8/8/7b/w7/8/8/8 w 1",
+            "8/8/8/7b/w7/8/8/8 b 1",
+            "6b1/8/8/8/8/w7/w7/8 w 3",
+            "8/8/b7/8/8/8/7w/8 b 1",
+        ] {
+            let node = BreakthroughNode::from_fen(fen).unwrap();
+            let proven = runner_win(&node).unwrap();
+            assert_eq!(proven, solve(&node, 12), "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    ffi::{c_char, CStr},
+    path::Path,
+    ptr,
+    time::Duration,
+};
+
+use crate::{
+    core::{move8::BreakthroughMove, movelist::MAX_MOVES, node8::BreakthroughNode, Evaluation, GameResult, Player},
+    evaluation::params::EvalParams,
+    search::searcher::{SearchLimits, Searcher},
+};
+
+/*
+ * C API for embedding the engine, e.g. in a tournament harness. Positions and
+ * searchers are opaque handles that the caller frees; everything else is passed
+ * by value. The header include/breakthrough.h is generated from this file with
+ * cbindgen, so the `///` comments here end up in it.
+ */
+
+// The longest principal variation a search result holds, also defined in cbindgen.toml
+pub const BT_MAX_PV: usize = 64;
+// The most legal moves a position can have, also defined in cbindgen.toml
+pub const BT_MAX_MOVES: usize = MAX_MOVES;
+
+/// A position, from `bt_position_new` or `bt_position_from_fen`
+pub struct BtPosition(BreakthroughNode);
+
+/// An engine with its own transposition table, from `bt_searcher_new`
+pub struct BtSearcher {
+    searcher: Searcher,
+    params: EvalParams,
+}
+
+/// Squares are numbered `row * 8 + col`, where row 0 is rank 8 and col 0 is file a
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BtMove {
+    pub from: u8,
+    pub to: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BtPlayer {
+    BtWhite,
+    BtBlack,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BtResult {
+    BtUndecided,
+    BtWhiteWins,
+    BtBlackWins,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BtEvalKind {
+    /// `value` is a score, positive when White is better
+    BtHeuristic,
+    /// `value` is the ply of the game at which White wins
+    BtWhiteWinPly,
+    /// `value` is the ply of the game at which Black wins
+    BtBlackWinPly,
+}
+
+/// An evaluation from White's point of view
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BtEval {
+    pub kind: BtEvalKind,
+    pub value: i64,
+}
+
+/// Zero means no limit for either field. The time limit only applies once depth 1
+/// is done, so a search always finds a move.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BtLimits {
+    pub depth: u32,
+    pub time_ms: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BtSearchResult {
+    pub best: BtMove,
+    pub eval: BtEval,
+    /// The deepest depth that finished
+    pub depth: u32,
+    pub elapsed_ms: u64,
+    /// The first `pv_len` moves of `pv` are the expected line, starting with `best`
+    pub pv_len: u32,
+    pub pv: [BtMove; BT_MAX_PV],
+}
+
+// Deep enough that a search without a depth limit runs until its time is up
+const UNLIMITED_DEPTH: u32 = 64;
+
+impl From<BreakthroughMove> for BtMove {
+    fn from(action: BreakthroughMove) -> Self {
+        Self { from: action.0, to: action.1 }
+    }
+}
+
+impl From<BtMove> for BreakthroughMove {
+    fn from(action: BtMove) -> Self {
+        BreakthroughMove(action.from, action.to)
+    }
+}
+
+impl From<Evaluation> for BtEval {
+    fn from(eval: Evaluation) -> Self {
+        match eval {
+            Evaluation::Heuristic(n) => Self { kind: BtEvalKind::BtHeuristic, value: n },
+            Evaluation::WhiteWinPly(ply) => Self { kind: BtEvalKind::BtWhiteWinPly, value: ply as i64 },
+            Evaluation::BlackWinPly(ply) => Self { kind: BtEvalKind::BtBlackWinPly, value: ply as i64 },
+        }
+    }
+}
+
+// A string from the caller, None if it's null or not UTF-8
+unsafe fn c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    match s.is_null() {
+        true => None,
+        false => CStr::from_ptr(s).to_str().ok(),
+    }
+}
+
+// Copy as much of `s` as fits into `buf` with a terminating NUL, returning the
+// length of all of `s` like snprintf
+unsafe fn write_c_str(s: &str, buf: *mut c_char, len: usize) -> usize {
+    if !buf.is_null() && len > 0 {
+        let n = s.len().min(len - 1);
+        ptr::copy_nonoverlapping(s.as_ptr() as *const c_char, buf, n);
+        *buf.add(n) = 0;
+    }
+    s.len()
+}
+
+/// The starting position
+#[no_mangle]
+pub extern "C" fn bt_position_new() -> *mut BtPosition {
+    Box::into_raw(Box::new(BtPosition(BreakthroughNode::default())))
+}
+
+/// A position from FEN, e.g. "bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1",
+/// or NULL if it's invalid.
+///
+/// # Safety
+/// `fen` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_from_fen(fen: *const c_char) -> *mut BtPosition {
+    match c_str(fen).map(BreakthroughNode::from_fen) {
+        Some(Ok(node)) => Box::into_raw(Box::new(BtPosition(node))),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `position` must be a live position handle.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_clone(position: *const BtPosition) -> *mut BtPosition {
+    Box::into_raw(Box::new(BtPosition((*position).0.clone())))
+}
+
+/// Writes the FEN into `buf`, truncated to `len` bytes including the NUL, and
+/// returns its full length.
+///
+/// # Safety
+/// `position` must be a live position handle and `buf` must be NULL or hold `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_fen(position: *const BtPosition, buf: *mut c_char, len: usize) -> usize {
+    write_c_str(&(*position).0.fen(), buf, len)
+}
+
+/// # Safety
+/// `position` must be a live position handle.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_to_play(position: *const BtPosition) -> BtPlayer {
+    match (*position).0.to_play {
+        Player::White => BtPlayer::BtWhite,
+        Player::Black => BtPlayer::BtBlack,
+    }
+}
+
+/// # Safety
+/// `position` must be a live position handle.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_result(position: *const BtPosition) -> BtResult {
+    match (*position).0.get_result() {
+        GameResult::Win(Player::White) => BtResult::BtWhiteWins,
+        GameResult::Win(Player::Black) => BtResult::BtBlackWins,
+        GameResult::Undecided => BtResult::BtUndecided,
+    }
+}
+
+/// Writes up to `capacity` legal moves into `moves` and returns how many there are
+/// in all, which is never more than `BT_MAX_MOVES`. A buffer of that size always
+/// holds them; otherwise call with a NULL buffer first to get the count. There are
+/// none once the game is over.
+///
+/// # Safety
+/// `position` must be a live position handle and `moves` must be NULL or hold
+/// `capacity` moves.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_legal_moves(position: *const BtPosition, moves: *mut BtMove, capacity: usize) -> usize {
+    let node = &(*position).0;
+    if node.is_terminal() {
+        return 0;
+    }
+    let list = node.moves();
+    if !moves.is_null() {
+        for (i, action) in list.iter().take(capacity).enumerate() {
+            *moves.add(i) = (*action).into();
+        }
+    }
+    list.len()
+}
+
+/// Plays a move, returning false and leaving the position alone if it's illegal.
+///
+/// # Safety
+/// `position` must be a live position handle.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_make_move(position: *mut BtPosition, action: BtMove) -> bool {
+    let node = &mut (*position).0;
+    let action = action.into();
+    if node.check_move(&action).is_err() {
+        return false;
+    }
+    *node = node.take_action(&action);
+    true
+}
+
+/// # Safety
+/// `position` must be NULL or a position handle that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bt_position_free(position: *mut BtPosition) {
+    if !position.is_null() {
+        drop(Box::from_raw(position));
+    }
+}
+
+/// Parses a move like "d2d3", returning false if it isn't one. Legality isn't checked.
+///
+/// # Safety
+/// `s` must be NULL or a NUL-terminated string, and `action` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn bt_move_parse(s: *const c_char, action: *mut BtMove) -> bool {
+    match c_str(s).map(str::parse::<BreakthroughMove>) {
+        Some(Ok(parsed)) => {
+            *action = parsed.into();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Writes a move like "d2d3" into `buf`, which needs 5 bytes for the whole move.
+/// Returns the length of the move text, or 0 with an empty string if a square is
+/// off the board.
+///
+/// # Safety
+/// `buf` must be NULL or hold `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bt_move_to_string(action: BtMove, buf: *mut c_char, len: usize) -> usize {
+    if action.from >= 64 || action.to >= 64 {
+        return write_c_str("", buf, len);
+    }
+    write_c_str(&BreakthroughMove::from(action).to_string(), buf, len)
+}
+
+/// A searcher with a table of `table_size` entries, using the evaluation parameters
+/// in the file at `params_path`, or the defaults if it's NULL. Returns NULL if the
+/// file can't be read.
+///
+/// # Safety
+/// `params_path` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bt_searcher_new(table_size: usize, params_path: *const c_char) -> *mut BtSearcher {
+    let params = match params_path.is_null() {
+        true => EvalParams::default(),
+        false => match c_str(params_path).map(|path| EvalParams::load(Path::new(path))) {
+            Some(Ok(params)) => params,
+            _ => return ptr::null_mut(),
+        },
+    };
+    Box::into_raw(Box::new(BtSearcher {
+        searcher: Searcher::new(table_size),
+        params,
+    }))
+}
+
+/// Searches `position` within `limits` and fills in `result`. Returns false, leaving
+/// `result` alone, if the game is already over or the side to play has no moves.
+///
+/// # Safety
+/// `searcher` and `position` must be live handles and `result` a valid pointer. A
+/// searcher must not be used by two threads at once.
+#[no_mangle]
+pub unsafe extern "C" fn bt_searcher_search(
+    searcher: *mut BtSearcher,
+    position: *const BtPosition,
+    limits: BtLimits,
+    result: *mut BtSearchResult,
+) -> bool {
+    let BtSearcher { searcher, params } = &mut *searcher;
+    let node = &(*position).0;
+    if node.is_terminal() || node.moves().is_empty() {
+        return false;
+    }
+    let limits = SearchLimits {
+        depth: match limits.depth {
+            0 => UNLIMITED_DEPTH,
+            depth => depth,
+        },
+        time: match limits.time_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        },
+    };
+    let Some(info) = searcher.search(node, limits, params, None, |_| {}) else {
+        return false;
+    };
+
+    let mut pv = [BtMove::default(); BT_MAX_PV];
+    for (slot, action) in pv.iter_mut().zip(&info.pv) {
+        *slot = (*action).into();
+    }
+    *result = BtSearchResult {
+        best: info.best.into(),
+        eval: info.eval.into(),
+        depth: info.depth,
+        elapsed_ms: info.elapsed.as_millis() as u64,
+        pv_len: info.pv.len().min(BT_MAX_PV) as u32,
+        pv,
+    };
+    true
+}
+
+/// # Safety
+/// `searcher` must be NULL or a searcher handle that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bt_searcher_free(searcher: *mut BtSearcher) {
+    if !searcher.is_null() {
+        drop(Box::from_raw(searcher));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    core::{error::IllegalMove, move8::BreakthroughMove, node8::BreakthroughNode, Evaluation},
+    record::{GameRecord, RecordedMove},
+};
+
+/*
+ * Game controller for interactive play: the moves played so far and the position
+ * after each one, with undo/redo and jumping to any ply of the line.
+ *
+ * Undone moves are kept until a different move is played, so they can be redone.
+ */
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    // Record tags and the whole line, including moves that were undone
+    record: GameRecord,
+    positions: Vec<BreakthroughNode>,
+    current: usize,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(BreakthroughNode::default())
+    }
+}
+
+impl Game {
+    pub fn new(start: BreakthroughNode) -> Self {
+        Self::from_record(GameRecord::new(start))
+    }
+
+    // Continue a recorded game from its last move
+    pub fn from_record(record: GameRecord) -> Self {
+        let positions = record.positions();
+        Self {
+            current: record.moves.len(),
+            record,
+            positions,
+        }
+    }
+
+    pub fn node(&self) -> &BreakthroughNode {
+        &self.positions[self.current]
+    }
+
+    pub fn start(&self) -> &BreakthroughNode {
+        &self.record.start
+    }
+
+    // Number of moves played from the start to reach the current position
+    pub fn ply(&self) -> usize {
+        self.current
+    }
+
+    // Number of moves in the line, counting undone moves that can be redone
+    pub fn line_len(&self) -> usize {
+        self.record.moves.len()
+    }
+
+    // The moves leading to the current position
+    pub fn history(&self) -> &[RecordedMove] {
+        &self.record.moves[..self.current]
+    }
+
+    // The whole line, which goes on past the current position after an undo
+    pub fn line(&self) -> &[RecordedMove] {
+        &self.record.moves
+    }
+
+    // The move that led to the current position
+    pub fn last_move(&self) -> Option<&BreakthroughMove> {
+        self.history().last().map(|recorded| &recorded.action)
+    }
+
+    // Play a move from the current position, dropping any undone moves after it
+    pub fn play(
+        &mut self,
+        action: BreakthroughMove,
+        eval: Option<Evaluation>,
+        comment: Option<String>,
+    ) -> Result<(), IllegalMove> {
+        self.node().check_move(&action)?;
+        let next = self.node().take_action(&action);
+        self.record.moves.truncate(self.current);
+        self.positions.truncate(self.current + 1);
+        self.record.push(action, eval, comment);
+        self.positions.push(next);
+        self.current += 1;
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current < self.line_len()
+    }
+
+    // Step back one move, returning false at the start of the game
+    pub fn undo(&mut self) -> bool {
+        self.jump(self.current.wrapping_sub(1))
+    }
+
+    // Step forward along the line after an undo, returning false at its end
+    pub fn redo(&mut self) -> bool {
+        self.jump(self.current + 1)
+    }
+
+    // Go to the position after `ply` moves, if the line is that long
+    pub fn jump(&mut self, ply: usize) -> bool {
+        if ply > self.line_len() {
+            return false;
+        }
+        self.current = ply;
+        true
+    }
+
+    // A record of the game up to the current position, with the result if it's over
+    pub fn record(&self) -> GameRecord {
+        let mut record = self.record.clone();
+        record.moves.truncate(self.current);
+        record.result = None;
+        record.finish();
+        record
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::core::{error::IllegalMove, move8::BreakthroughMove, node8::BreakthroughNode};
+
+    use super::Game;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for m in moves {
+            game.play(BreakthroughMove::from_str(m).unwrap(), None, None).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::default();
+        assert!(!game.undo());
+        play(&mut game, &["d2d3", "c7c6", "d3d4"]);
+        let end = game.node().clone();
+
+        assert!(game.undo() && game.undo());
+        assert_eq!((game.ply(), game.line_len()), (1, 3));
+        assert_eq!(game.history().len(), 1);
+        assert_eq!(game.record().moves.len(), 1);
+        assert!(game.redo() && game.redo() && !game.redo());
+        assert_eq!(game.node(), &end);
+
+        assert!(game.jump(0));
+        assert_eq!(game.node(), &BreakthroughNode::default());
+        assert!(!game.jump(4));
+
+        // A new move replaces the undone line
+        assert!(game.jump(1));
+        play(&mut game, &["e7e6"]);
+        assert_eq!((game.ply(), game.line_len()), (2, 2));
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn test_illegal_move_leaves_game() {
+        let mut game = Game::default();
+        play(&mut game, &["d2d3"]);
+        let error = game.play(BreakthroughMove::from_str("d3d4").unwrap(), None, None);
+        assert_eq!(error, Err(IllegalMove::NoOwnPiece));
+        assert_eq!((game.ply(), game.line_len()), (1, 1));
+    }
+}
@@ -1,3 +1,12 @@
+pub mod book;
 pub mod core;
+pub mod environment;
 pub mod evaluation;
+pub mod ffi;
+pub mod game;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod record;
+pub mod render;
 pub mod search;
+pub mod tuning;
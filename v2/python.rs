@@ -0,0 +1,229 @@
+use std::{path::PathBuf, time::Duration};
+
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+
+use crate::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, GameResult, Player},
+    evaluation::{self, params::EvalParams},
+    search::searcher::{SearchLimits, Searcher},
+};
+
+/*
+ * Python bindings, so the engine can be driven from notebooks and RL code:
+ *
+ *   import breakthrough
+ *   node = breakthrough.BreakthroughNode().apply("d2d3")
+ *   breakthrough.evaluate(node, depth=6, time_ms=500)["best"]
+ *
+ * Moves are strings like "d2d3" and evals are dicts like {"type": "heuristic",
+ * "value": 40}, from White's point of view, matching the serde and HTTP forms.
+ */
+
+#[pyclass(name = "BreakthroughNode", module = "breakthrough", frozen, eq, hash, skip_from_py_object)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PyNode(pub BreakthroughNode);
+
+#[pymethods]
+impl PyNode {
+    // The starting position unless a FEN is given
+    #[new]
+    #[pyo3(signature = (fen = None))]
+    fn new(fen: Option<&str>) -> PyResult<Self> {
+        match fen {
+            Some(fen) => Self::from_fen(fen),
+            None => Ok(Self(BreakthroughNode::default())),
+        }
+    }
+
+    #[staticmethod]
+    fn from_fen(fen: &str) -> PyResult<Self> {
+        BreakthroughNode::from_fen(fen)
+            .map(Self)
+            .map_err(|e| PyValueError::new_err(format!("Invalid FEN: {}", e)))
+    }
+
+    fn fen(&self) -> String {
+        self.0.fen()
+    }
+
+    // Empty once the game is over
+    fn legal_moves(&self) -> Vec<String> {
+        match self.0.is_terminal() {
+            true => Vec::new(),
+            false => self.0.moves().iter().map(|action| action.to_string()).collect(),
+        }
+    }
+
+    // The position after a move, raising ValueError if it's illegal
+    fn apply(&self, action: &str) -> PyResult<Self> {
+        let action: BreakthroughMove = action
+            .parse()
+            .map_err(|e| PyValueError::new_err(format!("Invalid move: {}", e)))?;
+        self.0
+            .check_move(&action)
+            .map_err(|e| PyValueError::new_err(format!("Illegal move {}: {}", action, e)))?;
+        Ok(Self(self.0.take_action(&action)))
+    }
+
+    // "white" or "black" once someone has won, otherwise None
+    fn result(&self) -> Option<&'static str> {
+        match self.0.get_result() {
+            GameResult::Win(player) => Some(player_name(&player)),
+            GameResult::Undecided => None,
+        }
+    }
+
+    #[getter]
+    fn to_play(&self) -> &'static str {
+        player_name(&self.0.to_play)
+    }
+
+    #[getter]
+    fn ply(&self) -> u32 {
+        self.0.ply
+    }
+
+    // Bitboards with bit `row * 8 + col` set for each piece, row 0 being rank 8
+    #[getter]
+    fn white(&self) -> u64 {
+        self.0.bitboard_white
+    }
+
+    #[getter]
+    fn black(&self) -> u64 {
+        self.0.bitboard_black
+    }
+
+    fn __repr__(&self) -> String {
+        format!("BreakthroughNode('{}')", self.0.fen())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+fn player_name(player: &Player) -> &'static str {
+    match player {
+        Player::White => "white",
+        Player::Black => "black",
+    }
+}
+
+fn eval_dict<'py>(py: Python<'py>, eval: &Evaluation) -> PyResult<Bound<'py, PyDict>> {
+    let (kind, value) = match eval {
+        Evaluation::Heuristic(n) => ("heuristic", *n),
+        Evaluation::WhiteWinPly(ply) => ("white_win_ply", *ply as i64),
+        Evaluation::BlackWinPly(ply) => ("black_win_ply", *ply as i64),
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("type", kind)?;
+    dict.set_item("value", value)?;
+    Ok(dict)
+}
+
+fn load_params(params: Option<PathBuf>) -> PyResult<EvalParams> {
+    match params {
+        Some(path) => EvalParams::load(&path).map_err(|e| PyIOError::new_err(format!("{}: {}", path.display(), e))),
+        None => Ok(EvalParams::default()),
+    }
+}
+
+// The static evaluation with a parameter file, or the default parameters
+#[pyfunction]
+#[pyo3(signature = (node, params = None))]
+fn fast_heuristic<'py>(py: Python<'py>, node: &PyNode, params: Option<PathBuf>) -> PyResult<Bound<'py, PyDict>> {
+    eval_dict(py, &evaluation::fast_heuristic(&node.0, &load_params(params)?))
+}
+
+// Search to `depth`, or until `time_ms` runs out once depth 1 is done. Returns a
+// dict with the best move, eval, depth reached and PV, or None if the game is over
+// or the side to play has no moves.
+#[pyfunction]
+#[pyo3(signature = (node, depth = 8, time_ms = None, params = None, table_size = 1 << 16))]
+fn evaluate<'py>(
+    py: Python<'py>,
+    node: &PyNode,
+    depth: u32,
+    time_ms: Option<u64>,
+    params: Option<PathBuf>,
+    table_size: usize,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    if node.0.is_terminal() || node.0.moves().is_empty() {
+        return Ok(None);
+    }
+    let mut params = load_params(params)?;
+    let limits = SearchLimits { depth, time: time_ms.map(Duration::from_millis) };
+    let node = node.0.clone();
+    // Other Python threads can run while the engine thinks
+    let info = py.detach(|| Searcher::new(table_size).search(&node, limits, &mut params, None, |_| {}));
+    let Some(info) = info else {
+        return Ok(None);
+    };
+
+    let dict = PyDict::new(py);
+    dict.set_item("best", info.best.to_string())?;
+    dict.set_item("eval", eval_dict(py, &info.eval)?)?;
+    dict.set_item("depth", info.depth)?;
+    dict.set_item("pv", info.pv.iter().map(|action| action.to_string()).collect::<Vec<_>>())?;
+    Ok(Some(dict))
+}
+
+#[pymodule]
+#[pyo3(name = "breakthrough")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyNode>()?;
+    m.add_function(wrap_pyfunction!(fast_heuristic, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pyo3::prelude::*;
+
+    use super::{evaluate, PyNode};
+
+    #[test]
+    fn test_node_methods() {
+        let node = PyNode::new(None).unwrap();
+        assert_eq!(node.legal_moves().len(), 22);
+        assert_eq!(node.to_play(), "white");
+        assert_eq!(node.white(), 0xffff << 48);
+
+        let node = node.apply("d2d3").unwrap();
+        assert_eq!(node.to_play(), "black");
+        assert_eq!(PyNode::from_fen(&node.fen()).unwrap(), node);
+        assert!(node.apply("d3d4").is_err());
+        assert!(PyNode::new(Some("8/8 w 1")).is_err());
+
+        let won = PyNode::from_fen("w7/8/8/8/8/8/7b/8 b 2").unwrap();
+        assert_eq!(won.result(), Some("white"));
+        assert!(won.legal_moves().is_empty());
+
+        // Not won yet, but White has no pieces left to move
+        let stuck = PyNode::from_fen("b7/8/8/8/8/8/8/8 w 1").unwrap();
+        assert_eq!(stuck.result(), None);
+        assert!(stuck.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        Python::initialize();
+        Python::attach(|py| {
+            let node = PyNode::new(None).unwrap();
+            let info = evaluate(py, &node, 2, None, None, 1 << 10).unwrap().unwrap();
+            assert_eq!(info.get_item("depth").unwrap().unwrap().extract::<u32>().unwrap(), 2);
+
+            // Neither won nor playable, so there's nothing to search
+            let stuck = PyNode::from_fen("b7/8/8/8/8/8/8/8 w 1").unwrap();
+            assert!(evaluate(py, &stuck, 2, None, None, 1 << 10).unwrap().is_none());
+            let won = PyNode::from_fen("w7/8/8/8/8/8/7b/8 b 2").unwrap();
+            assert!(evaluate(py, &won, 2, None, None, 1 << 10).unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::core::{
+    error::{position_in, ParseError, ParseErrorKind},
+    move8::BreakthroughMove,
+    node8::BreakthroughNode,
+    Evaluation, GameResult, Player,
+};
+
+pub mod sgf;
+
+/*
+ * PGN-like game records.
+ *
+ * A record is a block of `[Name "value"]` tags followed by the move text:
+ *
+ *   [White "v2 depth 8"]
+ *   [Black "human"]
+ *   [Date "2026.10.19"]
+ *   [Size "8"]
+ *   [Result "1-0"]
+ *
+ *   1. d2d3 {[%eval 350]} c7c6 {[%eval -20] out of book} 2. ... 1-0
+ *
+ * A `FEN` tag gives the start position when it isn't the usual one. Comments go in
+ * braces after a move, with an optional leading `[%eval x]` where x is a score from
+ * White's perspective, or `#w41`/`#b41` for a win by White/Black at ply 41.
+ * Within the text `\`, `}`, `[` and `]` are escaped with a `\`, so a comment can
+ * span lines without ending early or a line of it passing for a tag.
+ * Files may hold several records one after another, each starting with its tags.
+ */
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedMove {
+    pub action: BreakthroughMove,
+    pub eval: Option<Evaluation>,
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    pub white: String,
+    pub black: String,
+    pub date: String,
+    pub result: Option<Player>,
+    pub start: BreakthroughNode,
+    pub moves: Vec<RecordedMove>,
+    // Tags other than the ones above, kept in file order
+    pub tags: Vec<(String, String)>,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        Self::new(BreakthroughNode::default())
+    }
+}
+
+impl GameRecord {
+    pub fn new(start: BreakthroughNode) -> Self {
+        Self {
+            white: "?".to_string(),
+            black: "?".to_string(),
+            date: "????.??.??".to_string(),
+            result: None,
+            start,
+            moves: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, action: BreakthroughMove, eval: Option<Evaluation>, comment: Option<String>) {
+        self.moves.push(RecordedMove {
+            action,
+            eval,
+            comment,
+        });
+    }
+
+    // Every position of the game, from the start up to and including the last move
+    pub fn positions(&self) -> Vec<BreakthroughNode> {
+        let mut positions = vec![self.start.clone()];
+        for recorded in &self.moves {
+            let next = positions[positions.len() - 1].take_action(&recorded.action);
+            positions.push(next);
+        }
+        positions
+    }
+
+    pub fn final_node(&self) -> BreakthroughNode {
+        self.moves
+            .iter()
+            .fold(self.start.clone(), |node, recorded| node.take_action(&recorded.action))
+    }
+
+    // Set the result from the final position, if the game is over
+    pub fn finish(&mut self) {
+        if let GameResult::Win(player) = self.final_node().get_result() {
+            self.result = Some(player);
+        }
+    }
+
+    // Read every record in `text`, which may be empty
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, ParseError> {
+        let mut records = Vec::new();
+        let mut start = 0;
+        let mut offset = 0;
+        let mut in_moves = false;
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            // A tag line after move text starts the next record
+            if trimmed.starts_with('[') && in_moves {
+                records.push(text[start..offset].parse::<Self>().map_err(|e| e.offset(start))?);
+                start = offset;
+                in_moves = false;
+            }
+            if !trimmed.is_empty() && !trimmed.starts_with('[') {
+                in_moves = true;
+            }
+            offset += line.len();
+        }
+        if !text[start..].trim().is_empty() {
+            records.push(text[start..].parse::<Self>().map_err(|e| e.offset(start))?);
+        }
+        Ok(records)
+    }
+
+    pub fn load_all(path: &Path) -> Result<Vec<Self>, io::Error> {
+        Ok(Self::parse_all(&fs::read_to_string(path)?)?)
+    }
+
+    // Add this record to the end of a file, creating it if needed
+    pub fn append(&self, path: &Path) -> Result<(), io::Error> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", self)
+    }
+}
+
+// An error about `input`, a slice of the `outer` text being parsed
+fn error_at(outer: &str, kind: ParseErrorKind, input: &str) -> ParseError {
+    ParseError::new(kind, input, position_in(outer, input))
+}
+
+fn format_eval(eval: &Evaluation) -> String {
+    match eval {
+        Evaluation::Heuristic(n) => n.to_string(),
+        Evaluation::WhiteWinPly(ply) => format!("#w{}", ply),
+        Evaluation::BlackWinPly(ply) => format!("#b{}", ply),
+    }
+}
+
+fn parse_eval(outer: &str, s: &str) -> Result<Evaluation, ParseError> {
+    let invalid = || error_at(outer, ParseErrorKind::InvalidEval, s);
+    if let Some(n) = s.strip_prefix("#w") {
+        Ok(Evaluation::WhiteWinPly(n.parse().map_err(|_| invalid())?))
+    } else if let Some(n) = s.strip_prefix("#b") {
+        Ok(Evaluation::BlackWinPly(n.parse().map_err(|_| invalid())?))
+    } else {
+        s.parse().map(Evaluation::Heuristic).map_err(|_| invalid())
+    }
+}
+
+fn format_result(result: &Option<Player>) -> &'static str {
+    match result {
+        Some(Player::White) => "1-0",
+        Some(Player::Black) => "0-1",
+        None => "*",
+    }
+}
+
+fn escape_comment(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '}' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// A backslash before anything else is kept, as older records didn't escape
+fn unescape_comment(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next @ ('\\' | '}' | '[' | ']')) if c == '\\' => {
+                unescaped.push(*next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+// Where a comment body ends, at the first `}` that isn't escaped
+fn comment_end(body: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '}' => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+// Split a comment body into its eval annotation and the remaining text
+fn parse_comment(outer: &str, body: &str) -> Result<(Option<Evaluation>, Option<String>), ParseError> {
+    let body = body.trim();
+    let (eval, rest) = match body.strip_prefix("[%eval") {
+        Some(rest) => {
+            let end = rest.find(']').ok_or_else(|| error_at(outer, ParseErrorKind::Unterminated, body))?;
+            (Some(parse_eval(outer, rest[..end].trim())?), rest[end + 1..].trim())
+        }
+        None => (None, body),
+    };
+    let comment = if rest.is_empty() { None } else { Some(unescape_comment(rest)) };
+    Ok((eval, comment))
+}
+
+// Returns the tag name, its unescaped value and the raw value within `outer`
+fn parse_tag<'a>(outer: &str, line: &'a str) -> Result<(String, String, &'a str), ParseError> {
+    let invalid = || error_at(outer, ParseErrorKind::InvalidTag, line);
+    let inner = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).ok_or_else(invalid)?;
+    let (name, value) = inner.split_once(' ').ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((name.to_string(), value.replace("\\\"", "\""), value))
+}
+
+impl FromStr for GameRecord {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::default();
+        // The tags come first, everything after them is move text
+        let mut movetext = "";
+        let mut offset = 0;
+        for line in s.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('[') {
+                movetext = &s[offset..];
+                break;
+            }
+            offset += line.len();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let (name, value, raw) = parse_tag(s, trimmed)?;
+            match name.as_str() {
+                "White" => record.white = value,
+                "Black" => record.black = value,
+                "Date" => record.date = value,
+                "Size" if value != "8" => return Err(error_at(s, ParseErrorKind::UnsupportedSize, raw)),
+                "Size" | "Result" => (),
+                "FEN" => {
+                    record.start = BreakthroughNode::from_fen(&value).map_err(|e| e.offset(position_in(s, raw)))?
+                }
+                _ => record.tags.push((name, value)),
+            }
+        }
+
+        let mut node = record.start.clone();
+        let mut rest = movetext;
+        let mut terminated = false;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            if terminated {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                return Err(error_at(s, ParseErrorKind::TrailingMoves, &rest[..end]));
+            }
+            if let Some(body) = rest.strip_prefix('{') {
+                let end = comment_end(body).ok_or_else(|| error_at(s, ParseErrorKind::Unterminated, rest))?;
+                let last = record
+                    .moves
+                    .last_mut()
+                    .ok_or_else(|| error_at(s, ParseErrorKind::MisplacedComment, &rest[..end + 2]))?;
+                let (eval, comment) = parse_comment(s, &body[..end])?;
+                last.eval = eval.or(last.eval);
+                last.comment = match (last.comment.take(), comment) {
+                    (Some(a), Some(b)) => Some(format!("{} {}", a, b)),
+                    (a, b) => a.or(b),
+                };
+                rest = &body[end + 1..];
+                continue;
+            }
+
+            let end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+            let token = &rest[..end];
+            rest = &rest[end..];
+            match token {
+                "1-0" => (record.result, terminated) = (Some(Player::White), true),
+                "0-1" => (record.result, terminated) = (Some(Player::Black), true),
+                "*" => terminated = true,
+                // Move numbers, `1.` or `1...`
+                _ if token.ends_with('.') && token.trim_end_matches('.').parse::<u32>().is_ok() => (),
+                _ => {
+                    let action = BreakthroughMove::from_str(token).map_err(|e| e.offset(position_in(s, token)))?;
+                    if !node.is_legal(&action) {
+                        return Err(error_at(s, ParseErrorKind::IllegalMove, token));
+                    }
+                    node = node.take_action(&action);
+                    record.push(action, None, None);
+                }
+            }
+        }
+        Ok(record)
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tags = vec![
+            ("White".to_string(), self.white.clone()),
+            ("Black".to_string(), self.black.clone()),
+            ("Date".to_string(), self.date.clone()),
+            ("Size".to_string(), "8".to_string()),
+            ("Result".to_string(), format_result(&self.result).to_string()),
+        ];
+        if self.start != BreakthroughNode::default() {
+            tags.push(("FEN".to_string(), self.start.fen()));
+        }
+        tags.extend(self.tags.iter().cloned());
+        for (name, value) in tags {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+
+        // Wrap the move text like PGN does, at 80 columns
+        let mut tokens = Vec::new();
+        let mut node = self.start.clone();
+        for (i, recorded) in self.moves.iter().enumerate() {
+            // Move numbers stay on the same line as their move
+            let number = node.ply / 2 + 1;
+            tokens.push(match node.to_play {
+                Player::White => format!("{}. {}", number, recorded.action),
+                Player::Black if i == 0 => format!("{}... {}", number, recorded.action),
+                Player::Black => recorded.action.to_string(),
+            });
+            let mut comment = Vec::new();
+            if let Some(eval) = &recorded.eval {
+                comment.push(format!("[%eval {}]", format_eval(eval)));
+            }
+            if let Some(text) = &recorded.comment {
+                comment.push(escape_comment(text));
+            }
+            if !comment.is_empty() {
+                tokens.push(format!("{{{}}}", comment.join(" ")));
+            }
+            node = node.take_action(&recorded.action);
+        }
+        tokens.push(format_result(&self.result).to_string());
+
+        let mut width = 0;
+        for token in tokens {
+            if width > 0 && width + 1 + token.len() > 80 {
+                writeln!(f)?;
+                width = 0;
+            } else if width > 0 {
+                write!(f, " ")?;
+                width += 1;
+            }
+            write!(f, "{}", token)?;
+            width += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+// Today's date in the `yyyy.mm.dd` form used by the Date tag
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86400)
+        .unwrap_or(0) as i64;
+    // Civil date from days since the epoch, see howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::core::{error::ParseErrorKind, move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, Player};
+
+    use super::GameRecord;
+
+    fn sample() -> GameRecord {
+        let mut record = GameRecord {
+            white: "v2 depth 4".to_string(),
+            black: "someone \"quoted\"".to_string(),
+            date: "2026.10.19".to_string(),
+            tags: vec![("Event".to_string(), "test".to_string())],
+            ..GameRecord::default()
+        };
+        let mut node = BreakthroughNode::default();
+        for (i, mv) in ["d2d3", "c7c6", "d3d4", "c6c5"].iter().enumerate() {
+            let action = BreakthroughMove::from_str(mv).unwrap();
+            node = node.take_action(&action);
+            let eval = if i % 2 == 0 { Some(Evaluation::Heuristic(-35)) } else { None };
+            let comment = if i == 1 { Some("a comment".to_string()) } else { None };
+            record.push(action, eval, comment);
+        }
+        record.push(BreakthroughMove::from_str("d4c5").unwrap(), Some(Evaluation::WhiteWinPly(41)), None);
+        record
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = sample();
+        let text = record.to_string();
+        assert!(text.contains("[Result \"*\"]"));
+        assert!(text.contains("1. d2d3 {[%eval -35]} c7c6 {a comment} 2. d3d4"));
+        assert_eq!(GameRecord::from_str(&text).unwrap(), record);
+
+        // Records can start with Black to move and carry a result
+        let mut record = GameRecord::new(BreakthroughNode::from_fen("8/8/8/8/8/1b6/w7/8 b 12").unwrap());
+        record.push(BreakthroughMove::from_str("b3b2").unwrap(), Some(Evaluation::BlackWinPly(25)), None);
+        record.push(BreakthroughMove::from_str("a2a3").unwrap(), None, None);
+        record.push(BreakthroughMove::from_str("b2b1").unwrap(), None, None);
+        record.finish();
+        assert_eq!(record.result, Some(Player::Black));
+        let text = record.to_string();
+        assert!(text.contains("12... b3b2 {[%eval #b25]} 13. a2a3 b2b1 0-1"));
+        assert_eq!(GameRecord::from_str(&text).unwrap(), record);
+    }
+
+    #[test]
+    fn test_comment_escapes() {
+        // Every line could be mistaken for something else if written as it is
+        let comment = "[%eval 5] isn't an eval\n[Tag \"not a tag\"]\nends with } and \\";
+        let mut record = sample();
+        record.moves[3].comment = Some(comment.to_string());
+        let text = record.to_string();
+        assert!(text.contains("{\\[%eval 5\\] isn't an eval\n\\[Tag"), "{}", text);
+        assert_eq!(GameRecord::parse_all(&format!("{}\n{}", text, text)).unwrap(), vec![record.clone(), record]);
+
+        // Unescaped backslashes from older records are kept
+        let record = GameRecord::from_str("1. d2d3 {a\\b} *").unwrap();
+        assert_eq!(record.moves[0].comment.as_deref(), Some("a\\b"));
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let (a, b) = (sample(), GameRecord::default());
+        let text = format!("{}\n{}\n", a, b);
+        assert_eq!(GameRecord::parse_all(&text).unwrap(), vec![a, b]);
+        assert_eq!(GameRecord::parse_all("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        let error = |text: &str| {
+            let e = GameRecord::from_str(text).unwrap_err();
+            (e.kind(), e.input().to_string(), e.position())
+        };
+        assert_eq!(error("1. d2d5 *"), (ParseErrorKind::IllegalMove, "d2d5".to_string(), 3));
+        assert_eq!(error("1. d2d3 d2d3 *").0, ParseErrorKind::IllegalMove);
+        assert_eq!(error("1. d2d9 *"), (ParseErrorKind::InvalidSquare, "d9".to_string(), 5));
+        assert_eq!(error("1. d2d3 {unterminated *").0, ParseErrorKind::Unterminated);
+        assert_eq!(error("1. d2d3 {[%eval x]}").0, ParseErrorKind::InvalidEval);
+        assert_eq!(error("{too early} 1. d2d3 *").0, ParseErrorKind::MisplacedComment);
+        assert_eq!(error("[Size \"6\"]\n\n*"), (ParseErrorKind::UnsupportedSize, "6".to_string(), 7));
+        assert_eq!(error("[FEN \"8/8 w 1\"]\n\n*"), (ParseErrorKind::RowCount, "8/8".to_string(), 6));
+        assert_eq!(error("[White]\n\n*").0, ParseErrorKind::InvalidTag);
+        assert_eq!(error("1. d2d3 1-0 c7c6"), (ParseErrorKind::TrailingMoves, "c7c6".to_string(), 12));
+
+        // Positions are relative to the whole file
+        let text = format!("{}\n[White \"x\"]\n\n1. d2d5 *\n", GameRecord::default());
+        let e = GameRecord::parse_all(&text).unwrap_err();
+        assert_eq!(&text[e.position()..e.position() + 4], "d2d5");
+    }
+}
@@ -0,0 +1,353 @@
+use std::{fs, io, path::Path, str::FromStr};
+
+use crate::core::{
+    error::{ParseError, ParseErrorKind},
+    move8::BreakthroughMove,
+    node8::BreakthroughNode,
+    Player,
+};
+
+use super::GameRecord;
+
+/*
+ * Import of SGF game records, as exported by Little Golem and Ludii.
+ *
+ * Moves are stored as `W[gbfc]`: the from and to squares as column and row letters,
+ * `a` being the first column and the first row. Some sites count rows from the top
+ * like Go does and some from the bottom, so both are tried and whichever replays
+ * legally wins. Algebraic moves like `W[g2f3]` or `W[g2-f3]` are accepted too.
+ *
+ * The first player to move is White here, whatever color the file gives them, and
+ * only the main line of each game is kept.
+ */
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowOrigin {
+    // Row `a` is the top row, as in Go
+    Top,
+    // Row `a` is the first rank, where White starts
+    Bottom,
+}
+
+// One `;` node of the game tree, with its properties in file order along with
+// the position of their first value
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct SgfNode {
+    properties: Vec<(String, Vec<String>, usize)>,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.offset < self.bytes.len() && self.bytes[self.offset].is_ascii_whitespace() {
+            self.offset += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.offset).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(b) if b == byte => {
+                self.offset += 1;
+                Ok(())
+            }
+            Some(_) => {
+                let c = self.text[self.offset..].chars().next().unwrap_or_default();
+                let input = &self.text[self.offset..self.offset + c.len_utf8()];
+                Err(ParseError::new(ParseErrorKind::UnexpectedCharacter, input, self.offset))
+            }
+            None => Err(ParseError::new(ParseErrorKind::Unterminated, "", self.offset)),
+        }
+    }
+
+    // `(` followed by nodes and subtrees; only the first subtree is kept
+    fn game_tree(&mut self) -> Result<Vec<SgfNode>, ParseError> {
+        self.expect(b'(')?;
+        let mut nodes = Vec::new();
+        while self.peek() == Some(b';') {
+            self.offset += 1;
+            nodes.push(self.node()?);
+        }
+        let mut first = true;
+        while self.peek() == Some(b'(') {
+            let variation = self.game_tree()?;
+            if first {
+                nodes.extend(variation);
+                first = false;
+            }
+        }
+        self.expect(b')')?;
+        Ok(nodes)
+    }
+
+    fn node(&mut self) -> Result<SgfNode, ParseError> {
+        let mut node = SgfNode::default();
+        while let Some(b) = self.peek() {
+            if !b.is_ascii_alphabetic() {
+                break;
+            }
+            let start = self.offset;
+            while self.offset < self.bytes.len() && self.bytes[self.offset].is_ascii_alphabetic() {
+                self.offset += 1;
+            }
+            let name = &self.text[start..self.offset];
+            let mut values = Vec::new();
+            while self.peek() == Some(b'[') {
+                values.push(self.value()?);
+            }
+            if values.is_empty() {
+                return Err(ParseError::new(ParseErrorKind::MissingField, name, start));
+            }
+            // The first value starts right after its bracket
+            let position = self.text[start..].find('[').map_or(start, |i| start + i + 1);
+            node.properties.push((name.to_string(), values, position));
+        }
+        Ok(node)
+    }
+
+    // A bracketed value, where `\` escapes the next character
+    fn value(&mut self) -> Result<String, ParseError> {
+        self.expect(b'[')?;
+        let start = self.offset - 1;
+        let unterminated = || ParseError::new(ParseErrorKind::Unterminated, "[", start);
+        let mut value = Vec::new();
+        loop {
+            match self.bytes.get(self.offset) {
+                None => return Err(unterminated()),
+                Some(b']') => break,
+                Some(b'\\') => {
+                    self.offset += 1;
+                    match self.bytes.get(self.offset) {
+                        // Escaped newlines are soft line breaks
+                        Some(b'\n') => (),
+                        Some(b) => value.push(*b),
+                        None => return Err(unterminated()),
+                    }
+                }
+                Some(b) => value.push(*b),
+            }
+            self.offset += 1;
+        }
+        self.offset += 1;
+        Ok(String::from_utf8_lossy(&value).into_owned())
+    }
+}
+
+fn sgf_square(col: u8, row: u8, origin: RowOrigin) -> Option<u8> {
+    if !(b'a'..=b'h').contains(&col) || !(b'a'..=b'h').contains(&row) {
+        return None;
+    }
+    let row = match origin {
+        RowOrigin::Top => row - b'a',
+        RowOrigin::Bottom => 7 - (row - b'a'),
+    };
+    Some(row * 8 + col - b'a')
+}
+
+// Parse a single move value in either SGF or algebraic notation
+pub fn parse_move(value: &str, origin: RowOrigin) -> Result<BreakthroughMove, ParseError> {
+    let value = value.trim();
+    let bytes = value.as_bytes();
+    if bytes.len() == 4 && bytes.iter().all(|b| b.is_ascii_lowercase()) {
+        return match (
+            sgf_square(bytes[0], bytes[1], origin),
+            sgf_square(bytes[2], bytes[3], origin),
+        ) {
+            (Some(from), Some(to)) => Ok(BreakthroughMove(from, to)),
+            (None, _) => Err(ParseError::new(ParseErrorKind::InvalidSquare, &value[..2], 0)),
+            (_, None) => Err(ParseError::new(ParseErrorKind::InvalidSquare, &value[2..], 2)),
+        };
+    }
+    let algebraic: String = value.chars().filter(|c| *c != '-' && *c != 'x').collect();
+    BreakthroughMove::from_str(&algebraic).map_err(|e| ParseError::new(e.kind(), value, 0))
+}
+
+// Replay the main line of one game with the given convention
+fn build_record(nodes: &[SgfNode], origin: RowOrigin) -> Result<GameRecord, ParseError> {
+    let mut record = GameRecord::default();
+    let mut node = record.start.clone();
+    // Color letter the file uses for the first player, once known
+    let mut first_color: Option<&str> = None;
+    let (mut sgf_white, mut sgf_black) = (None, None);
+    let mut sgf_result = None;
+
+    for sgf_node in nodes {
+        for (name, values, position) in &sgf_node.properties {
+            let value = values[0].as_str();
+            let error = |kind| ParseError::new(kind, value, *position);
+            match name.as_str() {
+                "SZ" if value.trim() != "8" => return Err(error(ParseErrorKind::UnsupportedSize)),
+                "GM" | "FF" | "CA" | "AP" | "SZ" => (),
+                "PW" => sgf_white = Some(value.to_string()),
+                "PB" => sgf_black = Some(value.to_string()),
+                "DT" => record.date = value.replace('-', "."),
+                "RE" => sgf_result = Some(value.to_string()),
+                "C" => match record.moves.last_mut() {
+                    Some(last) => last.comment = Some(value.trim().to_string()),
+                    None => record.tags.push(("Comment".to_string(), value.trim().to_string())),
+                },
+                "W" | "B" => {
+                    let first = *first_color.get_or_insert(name.as_str());
+                    if (first == name) != (node.to_play == Player::White) {
+                        return Err(error(ParseErrorKind::PlayersDoNotAlternate));
+                    }
+                    if value.trim().eq_ignore_ascii_case("resign") {
+                        // The player to move resigned
+                        record.result = Some(match node.to_play {
+                            Player::White => Player::Black,
+                            Player::Black => Player::White,
+                        });
+                        continue;
+                    }
+                    let action = parse_move(value, origin).map_err(|e| e.offset(*position))?;
+                    if !node.is_legal(&action) {
+                        return Err(error(ParseErrorKind::IllegalMove));
+                    }
+                    node = node.take_action(&action);
+                    record.push(action, None, None);
+                }
+                _ => record.tags.push((name.clone(), value.to_string())),
+            }
+        }
+    }
+
+    // Map the file's colors onto ours, where the first player is White
+    let swapped = first_color == Some("B");
+    let (white, black) = if swapped { (sgf_black, sgf_white) } else { (sgf_white, sgf_black) };
+    if let Some(white) = white {
+        record.white = white;
+    }
+    if let Some(black) = black {
+        record.black = black;
+    }
+    if let Some(result) = sgf_result {
+        let winner = match result.trim().chars().next() {
+            Some('W') => Some(Player::White),
+            Some('B') => Some(Player::Black),
+            _ => None,
+        };
+        if let Some(winner) = winner {
+            record.result = Some(match (winner, swapped) {
+                (Player::White, true) => Player::Black,
+                (Player::Black, true) => Player::White,
+                (winner, false) => winner,
+            });
+        }
+    }
+    if record.result.is_none() {
+        record.finish();
+    }
+    Ok(record)
+}
+
+// Read every game of an SGF collection into game records
+pub fn parse_sgf(text: &str) -> Result<Vec<GameRecord>, ParseError> {
+    let mut parser = Parser {
+        text,
+        bytes: text.as_bytes(),
+        offset: 0,
+    };
+    let mut records = Vec::new();
+    while parser.peek().is_some() {
+        let nodes = parser.game_tree()?;
+        // If neither convention works, the one that got further is likelier to be right
+        let record = match build_record(&nodes, RowOrigin::Top) {
+            Ok(record) => record,
+            Err(top) => build_record(&nodes, RowOrigin::Bottom)
+                .map_err(|bottom| if bottom.position() > top.position() { bottom } else { top })?,
+        };
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub fn load_sgf(path: &Path) -> Result<Vec<GameRecord>, io::Error> {
+    Ok(parse_sgf(&fs::read_to_string(path)?)?)
+}
+
+// Every position of every game, e.g. for analysis or opening books
+pub fn sgf_positions(text: &str) -> Result<Vec<Vec<BreakthroughNode>>, ParseError> {
+    Ok(parse_sgf(text)?.iter().map(|record| record.positions()).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::core::{error::ParseErrorKind, move8::BreakthroughMove, Player};
+
+    use super::{parse_move, parse_sgf, sgf_positions, RowOrigin};
+
+    #[test]
+    fn test_parse_move() {
+        let d2d3 = BreakthroughMove::from_str("d2d3").unwrap();
+        assert_eq!(parse_move("dgdf", RowOrigin::Top).unwrap(), d2d3);
+        assert_eq!(parse_move("dbdc", RowOrigin::Bottom).unwrap(), d2d3);
+        assert_eq!(parse_move("d2-d3", RowOrigin::Top).unwrap(), d2d3);
+        assert_eq!(parse_move("d2d3", RowOrigin::Bottom).unwrap(), d2d3);
+        assert!(parse_move("dzdf", RowOrigin::Top).is_err());
+        assert!(parse_move("", RowOrigin::Top).is_err());
+    }
+
+    #[test]
+    fn test_parse_sgf() {
+        // Little Golem style, rows from the bottom, with a variation and a comment
+        let text = "(;FF[4]GM[breakthrough]SZ[8]PW[alice]PB[bob]DT[2020-01-02]EV[test]RE[W+]
+            ;W[dbdc];B[dgdf]C[a \\] comment]
+            (;W[dcdd];B[dfde])
+            (;W[aaab]))
+            (;PW[carol]PB[dave];B[dgdf];W[gbgc]RE[B+])";
+        let records = parse_sgf(text).unwrap();
+        assert_eq!(records.len(), 2);
+
+        let first = &records[0];
+        assert_eq!((first.white.as_str(), first.black.as_str()), ("alice", "bob"));
+        assert_eq!(first.date, "2020.01.02");
+        assert_eq!(first.result, Some(Player::White));
+        assert_eq!(first.tags, vec![("EV".to_string(), "test".to_string())]);
+        let moves: Vec<String> = first.moves.iter().map(|m| m.action.to_string()).collect();
+        assert_eq!(moves, vec!["d2d3", "d7d6", "d3d4", "d6d5"]);
+        assert_eq!(first.moves[1].comment.as_deref(), Some("a ] comment"));
+
+        // Black moved first in the file, so colors are swapped, and rows count from the top
+        let second = &records[1];
+        assert_eq!((second.white.as_str(), second.black.as_str()), ("dave", "carol"));
+        assert_eq!(second.result, Some(Player::White));
+        let moves: Vec<String> = second.moves.iter().map(|m| m.action.to_string()).collect();
+        assert_eq!(moves, vec!["d2d3", "g7g6"]);
+
+        let positions = sgf_positions(text).unwrap();
+        assert_eq!(positions[0].len(), 5);
+        assert_eq!(positions[1][2], second.final_node());
+    }
+
+    #[test]
+    fn test_invalid_sgf() {
+        let error = |text: &str| {
+            let e = parse_sgf(text).unwrap_err();
+            (e.kind(), e.input().to_string(), e.position())
+        };
+        assert_eq!(error("(;W[dbdd])"), (ParseErrorKind::IllegalMove, "dbdd".to_string(), 4));
+        assert_eq!(error("(;W[dbdc];B[dgdf];W[dcdc])").0, ParseErrorKind::IllegalMove);
+        assert_eq!(error("(;W[dbdc];W[ebec])").0, ParseErrorKind::PlayersDoNotAlternate);
+        assert_eq!(error("(;SZ[6];W[dbdc])"), (ParseErrorKind::UnsupportedSize, "6".to_string(), 5));
+        assert_eq!(error("(;W[dzdc])"), (ParseErrorKind::InvalidSquare, "dz".to_string(), 4));
+        assert_eq!(error("(;W[dbdc]").0, ParseErrorKind::Unterminated);
+        assert_eq!(error("(;C[unterminated)"), (ParseErrorKind::Unterminated, "[".to_string(), 3));
+        assert_eq!(error("(;W[dbdc]1)"), (ParseErrorKind::UnexpectedCharacter, "1".to_string(), 9));
+        assert_eq!(error("(;W[dbdc]C)").0, ParseErrorKind::MissingField);
+
+        // The error comes from the convention that replayed the most moves
+        let e = parse_sgf("(;W[dbdc];B[dgdf];W[dcdc])").unwrap_err();
+        assert_eq!((e.input(), e.position()), ("dcdc", 20));
+    }
+}
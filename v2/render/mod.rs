@@ -0,0 +1,164 @@
+use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode};
+
+pub mod svg;
+
+/*
+ * Text boards for the terminal, with rank and file labels:
+ *
+ *   8 B B B B B B B B
+ *   ...
+ *   1 W W W W W W W W
+ *     a b c d e f g h
+ *
+ * Unicode swaps the letters for pawns and Color paints a checkered board with
+ * ANSI escapes. The last move is highlighted: its start square is marked in the
+ * plain styles and both of its squares are tinted in Color.
+ */
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderStyle {
+    #[default]
+    Ascii,
+    Unicode,
+    Color,
+}
+
+const RESET: &str = "\x1b[0m";
+const LIGHT_SQUARE: &str = "\x1b[48;5;180m";
+const DARK_SQUARE: &str = "\x1b[48;5;137m";
+const LAST_MOVE_SQUARE: &str = "\x1b[48;5;143m";
+const WHITE_PIECE: &str = "\x1b[1;97m";
+const BLACK_PIECE: &str = "\x1b[1;30m";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BoardRenderer {
+    pub style: RenderStyle,
+    // Black's side at the bottom
+    pub flipped: bool,
+}
+
+impl BoardRenderer {
+    pub fn new(style: RenderStyle) -> Self {
+        Self {
+            style,
+            flipped: false,
+        }
+    }
+
+    pub fn render(&self, node: &BreakthroughNode, last_move: Option<&BreakthroughMove>) -> String {
+        let order: Vec<u8> = match self.flipped {
+            false => (0..8).collect(),
+            true => (0..8).rev().collect(),
+        };
+        let mut result = String::new();
+        for &row in &order {
+            result.push((b'8' - row) as char);
+            for &col in &order {
+                self.push_square(&mut result, node, row * 8 + col, last_move);
+            }
+            if self.style == RenderStyle::Color {
+                result.push(' ');
+                result.push_str(RESET);
+            }
+            result.push('\n');
+        }
+        result.push(' ');
+        for &col in &order {
+            result.push(' ');
+            result.push((b'a' + col) as char);
+        }
+        result
+    }
+
+    fn push_square(&self, result: &mut String, node: &BreakthroughNode, square: u8, last_move: Option<&BreakthroughMove>) {
+        let (white, black) = (
+            node.bitboard_white & (1 << square) != 0,
+            node.bitboard_black & (1 << square) != 0,
+        );
+        let moved_from = last_move.is_some_and(|m| m.0 == square);
+        let moved_to = last_move.is_some_and(|m| m.1 == square);
+        match self.style {
+            RenderStyle::Ascii | RenderStyle::Unicode => {
+                let glyphs = match self.style {
+                    RenderStyle::Unicode => ['♙', '♟', '·', '∘'],
+                    _ => ['W', 'B', '.', '*'],
+                };
+                result.push(' ');
+                result.push(match (white, black) {
+                    (true, _) => glyphs[0],
+                    (_, true) => glyphs[1],
+                    _ if moved_from => glyphs[3],
+                    _ => glyphs[2],
+                });
+            }
+            RenderStyle::Color => {
+                let background = if moved_from || moved_to {
+                    LAST_MOVE_SQUARE
+                } else if (square / 8 + square % 8).is_multiple_of(2) {
+                    LIGHT_SQUARE
+                } else {
+                    DARK_SQUARE
+                };
+                result.push_str(background);
+                result.push(' ');
+                match (white, black) {
+                    (true, _) => result.push_str(&format!("{}●", WHITE_PIECE)),
+                    (_, true) => result.push_str(&format!("{}●", BLACK_PIECE)),
+                    _ => result.push(' '),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode};
+
+    use super::{BoardRenderer, RenderStyle};
+
+    #[test]
+    fn test_render_ascii() {
+        let action = BreakthroughMove::from_str("d2d3").unwrap();
+        let node = BreakthroughNode::default().take_action(&action);
+        let mut renderer = BoardRenderer::default();
+        let board = renderer.render(&node, Some(&action));
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines[0], "8 B B B B B B B B");
+        assert_eq!(lines[5], "3 . . . W . . . .");
+        assert_eq!(lines[6], "2 W W W * W W W W");
+        assert_eq!(lines[8], "  a b c d e f g h");
+
+        renderer.flipped = true;
+        let board = renderer.render(&node, None);
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines[0], "1 W W W W W W W W");
+        assert_eq!(lines[1], "2 W W W W . W W W");
+        assert_eq!(lines[8], "  h g f e d c b a");
+    }
+
+    #[test]
+    fn test_render_styles() {
+        let node = BreakthroughNode::default();
+        let unicode = BoardRenderer::new(RenderStyle::Unicode).render(&node, None);
+        assert!(unicode.starts_with("8 ♟ ♟"));
+        assert!(unicode.contains("1 ♙ ♙"));
+
+        // Same layout once the escapes are taken out
+        let color = BoardRenderer::new(RenderStyle::Color).render(&node, None);
+        let mut plain = String::new();
+        let mut in_escape = false;
+        for c in color.chars() {
+            match c {
+                '\x1b' => in_escape = true,
+                'm' if in_escape => in_escape = false,
+                _ if !in_escape => plain.push(c),
+                _ => {}
+            }
+        }
+        assert_eq!(plain.lines().nth(4), Some("4                 "));
+        assert_eq!(plain.lines().last(), Some("  a b c d e f g h"));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode};
+
+/*
+ * SVG board diagrams for reports. The board has coordinates along the left and
+ * bottom edges, and can carry highlighted squares and arrows for a move or a
+ * whole line. Arrows after the first are drawn fainter, so a line reads in order.
+ */
+
+const SQUARE: i32 = 40;
+const MARGIN: i32 = 20;
+const SIZE: i32 = 8 * SQUARE + 2 * MARGIN;
+
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const HIGHLIGHT: &str = "#f7ec5e";
+const ARROW: &str = "#2d7dd2";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiagramOptions {
+    // Black's side at the bottom
+    pub flipped: bool,
+    pub highlights: Vec<u8>,
+    pub arrows: Vec<BreakthroughMove>,
+}
+
+pub fn svg_diagram(node: &BreakthroughNode, options: &DiagramOptions) -> String {
+    // Top left corner of a square on the drawing
+    let corner = |square: u8| {
+        let (row, col) = ((square / 8) as i32, (square % 8) as i32);
+        match options.flipped {
+            false => (MARGIN + col * SQUARE, MARGIN + row * SQUARE),
+            true => (MARGIN + (7 - col) * SQUARE, MARGIN + (7 - row) * SQUARE),
+        }
+    };
+    let center = |square: u8| {
+        let (x, y) = corner(square);
+        (x + SQUARE / 2, y + SQUARE / 2)
+    };
+
+    // Writing to a String can't fail, so the results are ignored throughout
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" viewBox="0 0 {SIZE} {SIZE}" font-family="sans-serif" font-size="12">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<defs><marker id="arrowhead" markerWidth="4" markerHeight="4" refX="2" refY="2" orient="auto"><path d="M0,0 L4,2 L0,4 z" fill="{ARROW}"/></marker></defs>"#
+    );
+    let _ = writeln!(svg, r#"<rect width="{SIZE}" height="{SIZE}" fill="white"/>"#);
+
+    for square in 0..64u8 {
+        let (x, y) = corner(square);
+        let fill = if options.highlights.contains(&square) {
+            HIGHLIGHT
+        } else if (square / 8 + square % 8).is_multiple_of(2) {
+            LIGHT_SQUARE
+        } else {
+            DARK_SQUARE
+        };
+        let _ = writeln!(svg, r#"<rect x="{x}" y="{y}" width="{SQUARE}" height="{SQUARE}" fill="{fill}"/>"#);
+    }
+
+    // Rank labels run down the left edge, file labels along the bottom
+    for i in 0..8u8 {
+        let (_, y) = center(i * 8);
+        let (x, _) = center(i);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            MARGIN / 2,
+            y,
+            (b'8' - i) as char
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            x,
+            SIZE - MARGIN / 2,
+            (b'a' + i) as char
+        );
+    }
+
+    for square in 0..64u8 {
+        let fill = if node.bitboard_white & (1 << square) != 0 {
+            "white"
+        } else if node.bitboard_black & (1 << square) != 0 {
+            "#222"
+        } else {
+            continue;
+        };
+        let (x, y) = center(square);
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{x}" cy="{y}" r="{}" fill="{fill}" stroke="black" stroke-width="1.5"/>"#,
+            SQUARE * 3 / 8
+        );
+    }
+
+    for (i, arrow) in options.arrows.iter().enumerate() {
+        let (x1, y1) = center(arrow.0);
+        let (x2, y2) = center(arrow.1);
+        // Stop short of the center so the head doesn't cover the target piece
+        let (x2, y2) = (x2 - (x2 - x1) / 4, y2 - (y2 - y1) / 4);
+        let opacity = 0.9 / (i + 1) as f64;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{ARROW}" stroke-width="6" stroke-linecap="round" opacity="{opacity:.2}" marker-end="url(#arrowhead)"/>"#
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode};
+
+    use super::{svg_diagram, DiagramOptions};
+
+    #[test]
+    fn test_svg_diagram() {
+        let node = BreakthroughNode::default();
+        let svg = svg_diagram(&node, &DiagramOptions::default());
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<circle").count(), 32);
+        // The board, plus one rect per square
+        assert_eq!(svg.matches("<rect").count(), 65);
+        assert_eq!(svg.matches("<text").count(), 16);
+        assert!(!svg.contains("<line"));
+
+        let options = DiagramOptions {
+            flipped: true,
+            highlights: vec![BreakthroughMove::parse_square("d3").unwrap()],
+            arrows: vec![
+                BreakthroughMove::from_str("d2d3").unwrap(),
+                BreakthroughMove::from_str("c7c6").unwrap(),
+            ],
+        };
+        let svg = svg_diagram(&node, &options);
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert_eq!(svg.matches("#f7ec5e").count(), 1);
+        // a8 is in the bottom right corner when flipped
+        assert!(svg.contains(r##"<rect x="300" y="300" width="40" height="40" fill="#f0d9b5"/>"##));
+    }
+}
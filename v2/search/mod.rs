@@ -1,40 +1,136 @@
-use crate::core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation};
+use crate::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation},
+    evaluation::Evaluator,
+};
 
-use self::table::TranspositionTable;
+use self::{
+    position::SearchPosition,
+    searcher::StopCondition,
+    table::{SearchTable, TranspositionTable},
+};
 
 pub mod negamax;
+pub mod position;
+pub mod searcher;
 pub mod table;
-mod zobrist;
+pub mod zobrist;
 
-pub fn evaluate_with_ttable(
+// The best move and its value for the side to play, None if it has no moves
+pub fn evaluate_with_ttable<E: Evaluator + ?Sized>(
     node: &BreakthroughNode,
     depth: u32,
+    evaluator: &mut E,
     table: &mut TranspositionTable,
-) -> (BreakthroughMove, Evaluation) {
+) -> Option<(BreakthroughMove, Evaluation)> {
+    evaluate_with_stop(node, depth, evaluator, table, &StopCondition::never())
+}
+
+pub fn evaluate<E: Evaluator + ?Sized>(
+    node: &BreakthroughNode,
+    depth: u32,
+    evaluator: &mut E,
+) -> Option<(BreakthroughMove, Evaluation)> {
+    evaluate_with_ttable(node, depth, evaluator, &mut TranspositionTable::new(0))
+}
+
+// Like `evaluate_with_ttable`, but None if `stop` ends the search first or the side
+// to play has no moves
+pub fn evaluate_with_stop<E: Evaluator + ?Sized, T: SearchTable + ?Sized>(
+    node: &BreakthroughNode,
+    depth: u32,
+    evaluator: &mut E,
+    table: &mut T,
+    stop: &StopCondition,
+) -> Option<(BreakthroughMove, Evaluation)> {
     let eval = negamax::negamax(
-        node,
+        &mut SearchPosition::new(node.clone()),
         depth,
         Evaluation::BlackWinPly(node.ply),
         Evaluation::WhiteWinPly(node.ply),
+        evaluator,
         table,
+        stop,
     );
     match eval.0 {
-        Some(e) => (e, eval.1),
-        // If no move is returned, just get any random result
-        None => (node.get_possible_actions()[0].clone(), eval.1),
+        _ if stop.stopped() => None,
+        Some(e) => Some((e, eval.1)),
+        // If no move is returned, find the move leading to the best child
+        None => best_action(node, depth, evaluator, table, stop).map(|action| (action, eval.1)),
     }
 }
 
-pub fn evaluate(node: &BreakthroughNode, depth: u32) -> (BreakthroughMove, Evaluation) {
-    let eval = negamax::negamax(
-        node,
-        depth,
-        Evaluation::BlackWinPly(node.ply),
-        Evaluation::WhiteWinPly(node.ply),
-        &mut TranspositionTable::new(0),
-    );
-    match eval.0 {
-        Some(e) => (e, eval.1),
-        None => (node.get_possible_actions()[0].clone(), eval.1),
+// The search returns no move when it cuts off early (e.g. table hits or an
+// immediate win), so search each child to recover the move behind the value
+fn best_action<E: Evaluator + ?Sized, T: SearchTable + ?Sized>(
+    node: &BreakthroughNode,
+    depth: u32,
+    evaluator: &mut E,
+    table: &mut T,
+    stop: &StopCondition,
+) -> Option<BreakthroughMove> {
+    let mut best: Option<(BreakthroughMove, Evaluation)> = None;
+    let mut position = SearchPosition::new(node.clone());
+    for action in node.get_possible_actions() {
+        evaluator.push(node, &action);
+        let undo = position.make_move(&action);
+        let eval = -negamax::negamax(
+            &mut position,
+            depth.saturating_sub(1),
+            Evaluation::BlackWinPly(node.ply),
+            Evaluation::WhiteWinPly(node.ply),
+            evaluator,
+            table,
+            stop,
+        )
+        .1;
+        position.unmake_move(undo);
+        evaluator.pop();
+        if stop.stopped() {
+            return None;
+        }
+        if best.as_ref().is_none_or(|(_, value)| eval > *value) {
+            best = Some((action, eval));
+        }
+    }
+    best.map(|(action, _)| action)
+}
+
+// The expected line of play: `best` and then the best moves stored in the table
+pub fn principal_variation<T: SearchTable + ?Sized>(
+    node: &BreakthroughNode,
+    best: BreakthroughMove,
+    table: &T,
+    max_len: usize,
+) -> Vec<BreakthroughMove> {
+    let mut pv = vec![best];
+    let mut node = node.take_action(&best);
+    while pv.len() < max_len && !node.is_terminal() {
+        match table.best_move(&node) {
+            Some(action) if node.is_legal(&action) => {
+                pv.push(action);
+                node = node.take_action(&action);
+            }
+            _ => break,
+        }
+    }
+    pv
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{core::node8::BreakthroughNode, evaluation::params::EvalParams};
+
+    use super::{evaluate_with_ttable, table::TranspositionTable};
+
+    #[test]
+    fn test_root_move_behind_table_hit() {
+        // The second search finds the root in the table and gets a value without a
+        // move, so the root has to find the move again rather than play any legal one
+        let node = BreakthroughNode::default();
+        let mut table = TranspositionTable::new(1 << 16);
+        let first = evaluate_with_ttable(&node, 4, &mut EvalParams::default(), &mut table).unwrap();
+        let again = evaluate_with_ttable(&node, 4, &mut EvalParams::default(), &mut table).unwrap();
+        assert_eq!(first, again);
+        assert_ne!(again.0, node.get_possible_actions()[0]);
     }
 }
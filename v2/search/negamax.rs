@@ -2,25 +2,25 @@ use std::cmp::max;
 
 use crate::{
     core::{
-        constants8::{BLACK_FIRST_ROW, BLACK_START, WHITE_FIRST_ROW, WHITE_START},
+        constants8::{BLACK_START, WHITE_START},
         move8::BreakthroughMove,
         node8::BreakthroughNode,
         Evaluation, GameResult, Player,
     },
-    evaluation::fast_heuristic,
-    search::table::TranspositionTable,
+    evaluation::{runners::runner_win, Evaluator},
+    search::{position::SearchPosition, searcher::StopCondition, table::SearchTable},
 };
 
 // Attempt to evaluate the current node
 // Return negative if the game is over since the current player has already lost
 // Otherwise return positive
-fn evaluate_result(node: &BreakthroughNode) -> Evaluation {
+fn evaluate_result<E: Evaluator + ?Sized>(node: &BreakthroughNode, evaluator: &mut E) -> Evaluation {
     match node.get_result() {
         GameResult::Win(Player::White) => Evaluation::BlackWinPly(node.ply),
         GameResult::Win(Player::Black) => Evaluation::BlackWinPly(node.ply),
         GameResult::Undecided => match node.to_play {
-            Player::White => fast_heuristic(node),
-            Player::Black => -fast_heuristic(node),
+            Player::White => evaluator.evaluate(node),
+            Player::Black => -evaluator.evaluate(node),
         },
     }
 }
@@ -46,95 +46,36 @@ fn fast_win(node: &BreakthroughNode) -> Option<Evaluation> {
     }
 }
 
-// Moves sorted by priority and filtered to remove obvious losing moves
-fn get_filtered_actions(node: &BreakthroughNode) -> Vec<BreakthroughMove> {
-    let actions: Vec<BreakthroughMove> = node
-        .get_possible_actions()
-        .into_iter()
-        .filter(|action| {
-            // If opponent is threatening a win, we can only recapture
-            match node.to_play {
-                Player::White => {
-                    if node.bitboard_black & WHITE_START == 0 {
-                        true
-                    } else {
-                        (1 << action.1) & node.bitboard_black & WHITE_START > 0
-                    }
-                }
-                Player::Black => {
-                    if node.bitboard_white & BLACK_START == 0 {
-                        true
-                    } else {
-                        (1 << action.1) & node.bitboard_white & BLACK_START > 0
-                    }
-                }
-            }
-        })
-        .collect();
-
-    actions
-}
-
-fn guess_priority(
-    source_square: u64,
-    target_square: u64,
-    opp_start: u64,
-    opp_side: u64,
-    self_base: u64,
-    any_bitboard: u64,
-) -> i32 {
-    if target_square & opp_start > 0 {
-        // Priority 0: Almost-winning moves
-        0
-    } else if target_square & any_bitboard > 0 {
-        // Priority 1: Captures
-        1
-    } else if target_square & opp_side > 0 {
-        // Priority 2: Entering the opponent's side of the board
-        2
-    } else if (source_square & self_base) > 0 {
-        // Avoid moving pieces from the start row unless necessary
-        100
+// Squares we may move to: if the opponent is threatening a win, we can only recapture
+fn allowed_targets(node: &BreakthroughNode) -> u64 {
+    let threats = match node.to_play {
+        Player::White => node.bitboard_black & WHITE_START,
+        Player::Black => node.bitboard_white & BLACK_START,
+    };
+    if threats == 0 {
+        !0
     } else {
-        50
+        threats
     }
 }
 
-fn prioritize_actions(node: &BreakthroughNode, actions: &mut [BreakthroughMove]) {
-    actions.sort_unstable_by_key(|action| {
-        let (opp_start, opp_side, self_base) = match node.to_play {
-            Player::White => (
-                BLACK_START,
-                BLACK_START | (BLACK_START << 16),
-                WHITE_FIRST_ROW,
-            ),
-            Player::Black => (
-                WHITE_START,
-                WHITE_START | (WHITE_START >> 16),
-                BLACK_FIRST_ROW,
-            ),
-        };
-        // Prioritize seen nodes before new ones
-        guess_priority(
-            1 << action.0,
-            1 << action.1,
-            opp_start,
-            opp_side,
-            self_base,
-            node.bitboard_white | node.bitboard_black,
-        )
-    });
-}
-
-pub fn negamax(
-    node: &BreakthroughNode,
+pub fn negamax<E: Evaluator + ?Sized, T: SearchTable + ?Sized>(
+    position: &mut SearchPosition,
     depth: u32,
     alpha: Evaluation,
     beta: Evaluation,
-    table: &mut TranspositionTable,
+    evaluator: &mut E,
+    table: &mut T,
+    stop: &StopCondition,
 ) -> (Option<BreakthroughMove>, Evaluation) {
+    // The value doesn't matter once stopped, the caller throws it away
+    if stop.should_stop() {
+        return (None, alpha);
+    }
+
+    let node = position.node();
     if node.is_terminal() || depth == 0 {
-        return (None, evaluate_result(node));
+        return (None, evaluate_result(node, evaluator));
     }
 
     // If we don't need a move, try non-move search pruning
@@ -143,34 +84,48 @@ pub fn negamax(
         return (None, eval);
     }
 
-    if let Some(entry) = table.get(node, depth) {
-        return (None, entry.2);
+    // An unstoppable runner decides the game, with an exact ply
+    if let Some(eval) = runner_win(node) {
+        return match node.to_play {
+            Player::White => (None, eval),
+            Player::Black => (None, -eval),
+        };
     }
 
-    let mut actions = get_filtered_actions(node);
+    if let Some(eval) = table.probe(position.hash(), node, depth) {
+        return (None, eval);
+    }
+
+    // Moves come out sorted by priority and filtered to remove obvious losing moves
+    let actions = node.staged_moves(allowed_targets(node));
     if actions.is_empty() {
         // If there's no reasonable actions, the opponent wins in the next turn
         // Add 2 since lose state is on our next turn
         return (None, Evaluation::BlackWinPly(node.ply + 2));
     }
-    // Skip expensive prioritization of near-leaf nodes
-    if depth > 1 {
-        prioritize_actions(node, &mut actions);
-    }
 
     let (mut alpha, beta) = (alpha, beta);
     let mut value = (None, Evaluation::BlackWinPly(node.ply));
     for action in actions.iter() {
-        let child = node.take_action(action);
+        evaluator.push(position.node(), action);
+        let undo = position.make_move(action);
         let eval = negamax(
-            &child,
+            position,
             depth - 1,
             -beta,
             -alpha,
+            evaluator,
             table,
+            stop,
         );
+        position.unmake_move(undo);
+        evaluator.pop();
+        // Don't let a partial search reach the table
+        if stop.stopped() {
+            return (None, alpha);
+        }
         if -eval.1 > value.1 {
-            value = (Some(action), -eval.1);
+            value = (Some(*action), -eval.1);
         }
         alpha = max(alpha, value.1);
         if alpha >= beta {
@@ -178,6 +133,6 @@ pub fn negamax(
         }
     }
 
-    table.put((node.clone(), depth, value.1));
-    (value.0.cloned(), value.1)
+    table.store(position.hash(), (position.node().clone(), depth, value.1, value.0));
+    value
 }
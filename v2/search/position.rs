@@ -0,0 +1,144 @@
+use crate::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Player},
+    search::zobrist::{board_hash, zobrist_keys},
+};
+
+/*
+ * Position for the search to walk the tree with. Moves are made and unmade in
+ * place instead of building a node per child, and the transposition table hash
+ * is updated with each move instead of recomputed from the bitboards.
+ */
+
+// What `unmake_move` needs to restore the position before `action`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Undo {
+    pub action: BreakthroughMove,
+    pub captured: Option<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchPosition {
+    node: BreakthroughNode,
+    hash: u64,
+}
+
+impl SearchPosition {
+    pub fn new(node: BreakthroughNode) -> Self {
+        Self {
+            hash: board_hash(&node),
+            node,
+        }
+    }
+
+    pub fn node(&self) -> &BreakthroughNode {
+        &self.node
+    }
+
+    // Same value as `board_hash(self.node())`
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // Play a legal move for the side to play
+    pub fn make_move(&mut self, action: &BreakthroughMove) -> Undo {
+        let (start, end) = (1 << action.0, 1 << action.1);
+        let node = &mut self.node;
+        let (own, other) = match node.to_play {
+            Player::White => (&mut node.bitboard_white, &mut node.bitboard_black),
+            Player::Black => (&mut node.bitboard_black, &mut node.bitboard_white),
+        };
+        let captured = (*other & end != 0).then_some(action.1);
+        *own = (*own & !start) | end;
+        *other &= !end;
+        let mover = node.to_play.clone();
+        node.to_play = opponent(&mover);
+        node.ply += 1;
+
+        let undo = Undo {
+            action: *action,
+            captured,
+        };
+        self.toggle_hash(&mover, &undo);
+        undo
+    }
+
+    // Take back the last move made, which `undo` must have come from
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let (start, end) = (1 << undo.action.0, 1 << undo.action.1);
+        let node = &mut self.node;
+        let mover = opponent(&node.to_play);
+        let (own, other) = match mover {
+            Player::White => (&mut node.bitboard_white, &mut node.bitboard_black),
+            Player::Black => (&mut node.bitboard_black, &mut node.bitboard_white),
+        };
+        *own = (*own & !end) | start;
+        if undo.captured.is_some() {
+            *other |= end;
+        }
+        node.to_play = mover.clone();
+        node.ply -= 1;
+
+        self.toggle_hash(&mover, &undo);
+    }
+
+    // Zobrist keys are XORed in and out, so the same update makes and unmakes a move
+    fn toggle_hash(&mut self, mover: &Player, undo: &Undo) {
+        let keys = zobrist_keys();
+        let (from, to) = (undo.action.0 as usize, undo.action.1 as usize);
+        let (own, other) = match mover {
+            Player::White => (128, 64),
+            Player::Black => (64, 128),
+        };
+        self.hash ^= keys[own + from] ^ keys[from];
+        self.hash ^= match undo.captured {
+            Some(_) => keys[other + to],
+            None => keys[to],
+        };
+        self.hash ^= keys[own + to];
+    }
+}
+
+fn opponent(player: &Player) -> Player {
+    match player {
+        Player::White => Player::Black,
+        Player::Black => Player::White,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{seq::SliceRandom, SeedableRng};
+    use rand_chacha::ChaCha12Rng;
+
+    use crate::{core::node8::BreakthroughNode, search::zobrist::board_hash};
+
+    use super::SearchPosition;
+
+    #[test]
+    fn test_make_unmake_matches_take_action() {
+        let mut rng = ChaCha12Rng::seed_from_u64(40);
+        for _ in 0..20 {
+            let mut position = SearchPosition::new(BreakthroughNode::default());
+            let mut history = Vec::new();
+            while !position.node().is_terminal() {
+                // Every move round trips, then a random one is kept
+                let before = position.clone();
+                for action in position.node().get_possible_actions() {
+                    let undo = position.make_move(&action);
+                    assert_eq!(position.node(), &before.node().take_action(&action));
+                    assert_eq!(position.hash(), board_hash(position.node()));
+                    position.unmake_move(undo);
+                    assert_eq!(position, before);
+                }
+                let action = *position.node().get_possible_actions().choose(&mut rng).unwrap();
+                history.push((position.clone(), position.make_move(&action)));
+            }
+            // And the whole game unwinds back to the start
+            while let Some((before, undo)) = history.pop() {
+                position.unmake_move(undo);
+                assert_eq!(position, before);
+            }
+            assert_eq!(position.node(), &BreakthroughNode::default());
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, Player},
+    evaluation::Evaluator,
+    search::{
+        evaluate_with_stop, principal_variation,
+        table::{SearchTable, TranspositionTable},
+    },
+};
+
+/*
+ * Iterative deepening for interactive use: the search can be stopped from another
+ * thread or by a time limit, and reports the result of each finished depth as it goes.
+ */
+
+// How often the clock is read, in nodes, since reading it costs about as much as a node
+const CLOCK_INTERVAL: u32 = 1024;
+
+// When a running search should give up. Results from a stopped search are incomplete
+// and are thrown away.
+pub struct StopCondition<'a> {
+    flag: Option<&'a AtomicBool>,
+    deadline: Option<Instant>,
+    nodes: Cell<u32>,
+    expired: Cell<bool>,
+}
+
+impl<'a> StopCondition<'a> {
+    pub fn new(flag: Option<&'a AtomicBool>, deadline: Option<Instant>) -> Self {
+        Self {
+            flag,
+            deadline,
+            nodes: Cell::new(0),
+            expired: Cell::new(false),
+        }
+    }
+
+    pub fn never() -> Self {
+        Self::new(None, None)
+    }
+
+    // Called once per node, checking the clock every so often
+    pub fn should_stop(&self) -> bool {
+        if let Some(deadline) = self.deadline {
+            let nodes = self.nodes.get().wrapping_add(1);
+            self.nodes.set(nodes);
+            if nodes.is_multiple_of(CLOCK_INTERVAL) && Instant::now() >= deadline {
+                self.expired.set(true);
+            }
+        }
+        self.stopped()
+    }
+
+    // Whether the search has been stopped, without counting a node
+    pub fn stopped(&self) -> bool {
+        self.expired.get() || self.flag.is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: u32,
+    pub time: Option<Duration>,
+}
+
+// The result of one finished depth
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub best: BreakthroughMove,
+    // From White's point of view, like the evals in game records
+    pub eval: Evaluation,
+    // Starts with the best move
+    pub pv: Vec<BreakthroughMove>,
+    pub elapsed: Duration,
+}
+
+pub struct Searcher<T: SearchTable = TranspositionTable> {
+    table: T,
+}
+
+impl Searcher {
+    pub fn new(table_size: usize) -> Self {
+        Self::with_table(TranspositionTable::new(table_size))
+    }
+}
+
+impl<T: SearchTable> Searcher<T> {
+    // Search with a given table, e.g. a `SharedTable` used by other searchers too
+    pub fn with_table(table: T) -> Self {
+        Self { table }
+    }
+
+    // Search one depth at a time up to the limits, calling `on_info` after each.
+    // Returns the deepest finished result, or None if stopped before depth 1 was done
+    // or there's no move to search.
+    // The time limit only applies once there's a move to play.
+    pub fn search<E: Evaluator + ?Sized, F: FnMut(&SearchInfo)>(
+        &mut self,
+        node: &BreakthroughNode,
+        limits: SearchLimits,
+        evaluator: &mut E,
+        stop: Option<&AtomicBool>,
+        mut on_info: F,
+    ) -> Option<SearchInfo> {
+        let started = Instant::now();
+        let mut last = None;
+        for depth in 1..=limits.depth {
+            let deadline = match last {
+                Some(_) => limits.time.map(|time| started + time),
+                None => None,
+            };
+            let condition = StopCondition::new(stop, deadline);
+            let Some((best, eval)) = evaluate_with_stop(node, depth, evaluator, &mut self.table, &condition) else {
+                break;
+            };
+            let info = SearchInfo {
+                depth,
+                best,
+                eval: match node.to_play {
+                    Player::White => eval,
+                    Player::Black => -eval,
+                },
+                pv: principal_variation(node, best, &self.table, depth as usize),
+                elapsed: started.elapsed(),
+            };
+            on_info(&info);
+            last = Some(info);
+            if matches!(eval, Evaluation::WhiteWinPly(_) | Evaluation::BlackWinPly(_)) {
+                break;
+            }
+        }
+        last
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::atomic::AtomicBool, time::Duration};
+
+    use crate::{
+        core::node8::BreakthroughNode,
+        evaluation::params::EvalParams,
+        search::{
+            evaluate_with_ttable,
+            table::{SharedTable, TranspositionTable},
+        },
+    };
+
+    use super::{SearchLimits, Searcher};
+
+    #[test]
+    fn test_searcher_matches_fixed_depth() {
+        let node = BreakthroughNode::default().take_action(&"d2d3".parse().unwrap());
+        let mut params = EvalParams::default();
+        let mut depths = Vec::new();
+        let limits = SearchLimits { depth: 4, time: None };
+        let info = Searcher::new(1 << 16)
+            .search(&node, limits, &mut params, None, |info| depths.push(info.depth))
+            .unwrap();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+
+        let (best, eval) = evaluate_with_ttable(&node, 4, &mut params, &mut TranspositionTable::new(1 << 16)).unwrap();
+        assert_eq!((info.best, info.eval), (best, -eval));
+        assert_eq!(info.pv[0], best);
+        assert!(info.pv.len() <= 4);
+        // The line is playable
+        info.pv.iter().fold(node, |node, action| {
+            assert!(node.is_legal(action));
+            node.take_action(action)
+        });
+    }
+
+    #[test]
+    fn test_searcher_stops() {
+        let node = BreakthroughNode::default();
+        let mut params = EvalParams::default();
+        let stop = AtomicBool::new(true);
+        let limits = SearchLimits { depth: 30, time: None };
+        assert_eq!(Searcher::new(0).search(&node, limits, &mut params, Some(&stop), |_| {}), None);
+
+        // Time runs out long before depth 30
+        let limits = SearchLimits { depth: 30, time: Some(Duration::from_millis(50)) };
+        let info = Searcher::new(1 << 16).search(&node, limits, &mut params, None, |_| {}).unwrap();
+        assert!(info.depth < 30);
+
+        // White has no pieces left, so nothing to search
+        let node = BreakthroughNode::from_fen("b7/8/8/8/8/8/8/8 w 1").unwrap();
+        assert_eq!(Searcher::new(0).search(&node, limits, &mut params, None, |_| {}), None);
+    }
+
+    #[test]
+    fn test_searchers_share_table() {
+        let node = BreakthroughNode::default();
+        let mut params = EvalParams::default();
+        let limits = SearchLimits { depth: 4, time: None };
+        let alone = Searcher::new(1 << 16).search(&node, limits, &mut params, None, |_| {}).unwrap();
+
+        let table = SharedTable::new(1 << 16);
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let table = table.clone();
+                std::thread::spawn(move || {
+                    let mut params = EvalParams::default();
+                    Searcher::with_table(table).search(&BreakthroughNode::default(), limits, &mut params, None, |_| {}).unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            let info = handle.join().unwrap();
+            assert_eq!((info.best, info.eval), (alone.best, alone.eval));
+        }
+        // Both searches wrote to the one table
+        assert!(table.stats().1 > 0);
+    }
+}
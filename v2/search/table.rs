@@ -1,9 +1,19 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
 use crate::{
-    core::{node8::BreakthroughNode, Evaluation},
-    search::zobrist::zobrist_keys,
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation},
+    search::zobrist::board_hash,
 };
 
-type Entry = (BreakthroughNode, u32, Evaluation);
+// Position, depth searched, value and the best move if the search found one
+pub type Entry = (BreakthroughNode, u32, Evaluation, Option<BreakthroughMove>);
+
+// What the search needs from a table, so that it can also use one shared between threads
+pub trait SearchTable {
+    fn probe(&self, hash: u64, node: &BreakthroughNode, depth: u32) -> Option<Evaluation>;
+    fn store(&mut self, hash: u64, entry: Entry);
+    fn best_move(&self, node: &BreakthroughNode) -> Option<BreakthroughMove>;
+}
 
 pub struct TranspositionTable {
     capacity: usize,
@@ -26,24 +36,11 @@ impl TranspositionTable {
     // We don't need to hash to-play since positions are unique per player
     // If they weren't, players could make an infinite loop
     fn hash(node: &BreakthroughNode) -> u64 {
-        let keys = zobrist_keys();
-        (0..64)
-            .map(|i| {
-                match (
-                    node.bitboard_white & (1 << i),
-                    node.bitboard_black & (1 << i),
-                ) {
-                    (0, 0) => keys[i],
-                    (0, _) => keys[64 + i],
-                    (_, _) => keys[128 + i],
-                }
-            })
-            .reduce(|a, b| a ^ b)
-            .unwrap_or(0)
+        board_hash(node)
     }
 
-    fn get_with_index<'a>(&'a self, node: &BreakthroughNode) -> (usize, &'a Option<Entry>) {
-        let index: usize = (Self::hash(node) as usize) % self.capacity;
+    fn get_with_index(&self, hash: u64) -> (usize, &Option<Entry>) {
+        let index: usize = (hash as usize) % self.capacity;
         (
             index,
             self.table.get(index).expect("index should be in bounds"),
@@ -51,11 +48,16 @@ impl TranspositionTable {
     }
 
     pub fn get(&self, node: &BreakthroughNode, depth: u32) -> Option<&Entry> {
+        self.get_hashed(Self::hash(node), node, depth)
+    }
+
+    // Look up a node whose hash is already known, e.g. from a `SearchPosition`
+    pub fn get_hashed(&self, hash: u64, node: &BreakthroughNode, depth: u32) -> Option<&Entry> {
         if self.capacity == 0 {
             return None;
         }
 
-        let result = self.get_with_index(node).1;
+        let result = self.get_with_index(hash).1;
         match result {
             None => None,
             Some(entry) => {
@@ -73,12 +75,27 @@ impl TranspositionTable {
         }
     }
 
+    // The best move stored for `node` at any depth
+    pub fn best_move(&self, node: &BreakthroughNode) -> Option<BreakthroughMove> {
+        if self.capacity == 0 {
+            return None;
+        }
+        match self.get_with_index(Self::hash(node)).1 {
+            Some(entry) if &entry.0 == node => entry.3,
+            _ => None,
+        }
+    }
+
     pub fn put(&mut self, entry: Entry) {
+        self.put_hashed(Self::hash(&entry.0), entry)
+    }
+
+    pub fn put_hashed(&mut self, hash: u64, entry: Entry) {
         if self.capacity == 0 {
             return;
         }
 
-        let (index, current) = self.get_with_index(&entry.0);
+        let (index, current) = self.get_with_index(hash);
         match current {
             None => {
                 self.table[index] = Some(entry);
@@ -99,3 +116,51 @@ impl TranspositionTable {
         (self.capacity, self.occupied, self.collisions)
     }
 }
+
+impl SearchTable for TranspositionTable {
+    fn probe(&self, hash: u64, node: &BreakthroughNode, depth: u32) -> Option<Evaluation> {
+        self.get_hashed(hash, node, depth).map(|entry| entry.2)
+    }
+
+    fn store(&mut self, hash: u64, entry: Entry) {
+        self.put_hashed(hash, entry)
+    }
+
+    fn best_move(&self, node: &BreakthroughNode) -> Option<BreakthroughMove> {
+        TranspositionTable::best_move(self, node)
+    }
+}
+
+// A table for searches running at the same time on several threads. Each probe
+// and store holds the lock only for that one access.
+#[derive(Clone)]
+pub struct SharedTable(Arc<Mutex<TranspositionTable>>);
+
+impl SharedTable {
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(TranspositionTable::new(capacity))))
+    }
+
+    // A panic elsewhere can't leave an entry half written, so a poisoned lock is fine to use
+    fn lock(&self) -> MutexGuard<'_, TranspositionTable> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn stats(&self) -> (usize, usize, usize) {
+        self.lock().stats()
+    }
+}
+
+impl SearchTable for SharedTable {
+    fn probe(&self, hash: u64, node: &BreakthroughNode, depth: u32) -> Option<Evaluation> {
+        self.lock().probe(hash, node, depth)
+    }
+
+    fn store(&mut self, hash: u64, entry: Entry) {
+        self.lock().store(hash, entry)
+    }
+
+    fn best_move(&self, node: &BreakthroughNode) -> Option<BreakthroughMove> {
+        self.lock().best_move(node)
+    }
+}
@@ -3,13 +3,43 @@ use std::sync::OnceLock;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha12Rng;
 
-static ZOBRIST_KEYS: OnceLock<[u64; 192]> = OnceLock::new();
+use crate::core::{node8::BreakthroughNode, Player};
 
-pub fn zobrist_keys() -> &'static [u64; 192] {
+// One key per (empty, black, white) state of each square, plus one for Black to play
+static ZOBRIST_KEYS: OnceLock<[u64; 193]> = OnceLock::new();
+
+pub fn zobrist_keys() -> &'static [u64; 193] {
     ZOBRIST_KEYS.get_or_init(|| {
         let mut rng = ChaCha12Rng::seed_from_u64(3141592653589793238);
-        let mut result = [0u64; 192];
-        (0..192).for_each(|i| result[i] = rng.gen());
+        let mut result = [0u64; 193];
+        (0..193).for_each(|i| result[i] = rng.gen());
         result
     })
 }
+
+// Hash of the pieces alone, as used by the transposition table
+pub fn board_hash(node: &BreakthroughNode) -> u64 {
+    let keys = zobrist_keys();
+    (0..64)
+        .map(|i| {
+            match (
+                node.bitboard_white & (1 << i),
+                node.bitboard_black & (1 << i),
+            ) {
+                (0, 0) => keys[i],
+                (0, _) => keys[64 + i],
+                (_, _) => keys[128 + i],
+            }
+        })
+        .reduce(|a, b| a ^ b)
+        .unwrap_or(0)
+}
+
+// Hash of the pieces and the side to play. The keys are fixed, so this is
+// stable across runs and can be stored on disk.
+pub fn position_hash(node: &BreakthroughNode) -> u64 {
+    match node.to_play {
+        Player::White => board_hash(node),
+        Player::Black => board_hash(node) ^ zobrist_keys()[192],
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    core::{move8::BreakthroughMove, node8::BreakthroughNode, Evaluation, GameResult, Player},
+    evaluation::Evaluator,
+    search::{evaluate_with_ttable, table::TranspositionTable},
+};
+
+use super::TrainingPosition;
+
+/*
+ * Training data generation from self-play
+ */
+
+// Score recorded for a proven win, minus the number of plies until it happens
+pub const WIN_SCORE: i64 = 1_000_000;
+
+#[derive(Clone, Debug)]
+pub struct DatagenConfig {
+    // Search depth for every move
+    pub depth: u32,
+    // Uniformly random moves played before the engine takes over
+    pub random_plies: u32,
+    // Transposition table entries per game
+    pub table_size: usize,
+}
+
+impl Default for DatagenConfig {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            random_plies: 8,
+            table_size: 2usize.pow(18),
+        }
+    }
+}
+
+// Convert a search result for the side to play into a score from White's
+// perspective, mapping proven wins to large scores that prefer shorter wins
+pub fn white_score(node: &BreakthroughNode, eval: Evaluation) -> i64 {
+    let eval = match node.to_play {
+        Player::White => eval,
+        Player::Black => -eval,
+    };
+    match eval {
+        Evaluation::Heuristic(n) => n,
+        Evaluation::WhiteWinPly(ply) => WIN_SCORE - ply.saturating_sub(node.ply) as i64,
+        Evaluation::BlackWinPly(ply) => -WIN_SCORE + ply.saturating_sub(node.ply) as i64,
+    }
+}
+
+// Uniformly random moves from the starting position, stopping early if the game ends
+pub fn random_opening<R: Rng + ?Sized>(plies: u32, rng: &mut R) -> BreakthroughNode {
+    let mut node = BreakthroughNode::default();
+    for _ in 0..plies {
+        if node.is_terminal() {
+            break;
+        }
+        match node.moves().choose(rng) {
+            Some(action) => node = node.take_action(action),
+            None => break,
+        }
+    }
+    node
+}
+
+// Play from `start` until someone wins, with `search` choosing every move, and
+// return the winner. A side that `search` finds no move for has none and loses.
+pub fn play_out<F>(start: &BreakthroughNode, mut search: F) -> Player
+where
+    F: FnMut(&BreakthroughNode) -> Option<BreakthroughMove>,
+{
+    let mut node = start.clone();
+    loop {
+        if let GameResult::Win(player) = node.get_result() {
+            return player;
+        }
+        match search(&node) {
+            Some(action) => node = node.take_action(&action),
+            None => {
+                return match node.to_play {
+                    Player::White => Player::Black,
+                    Player::Black => Player::White,
+                }
+            }
+        }
+    }
+}
+
+// Play one game from a random opening, recording every searched position with
+// its score and the final winner
+pub fn play_game<E: Evaluator + ?Sized>(
+    config: &DatagenConfig,
+    evaluator: &mut E,
+    seed: u64,
+) -> Vec<TrainingPosition> {
+    let start = random_opening(config.random_plies, &mut ChaCha12Rng::seed_from_u64(seed));
+
+    let mut table = TranspositionTable::new(config.table_size);
+    let mut scored = Vec::new();
+    let winner = play_out(&start, |node| {
+        let (action, eval) = evaluate_with_ttable(node, config.depth, evaluator, &mut table)?;
+        scored.push((node.clone(), white_score(node, eval)));
+        Some(action)
+    });
+
+    scored
+        .into_iter()
+        .map(|(node, score)| TrainingPosition {
+            node,
+            score: Some(score),
+            winner: winner.clone(),
+        })
+        .collect()
+}
+
+// Play `games` games across `threads` workers and write each position as a line
+// to `output`, returning the number of positions written. Game i uses seed + i,
+// so the set of games is reproducible even though their order in the output isn't.
+pub fn generate<E, F, W>(
+    config: &DatagenConfig,
+    games: u64,
+    threads: usize,
+    seed: u64,
+    make_evaluator: F,
+    output: &mut W,
+) -> Result<usize, io::Error>
+where
+    E: Evaluator,
+    F: Fn() -> E + Sync,
+    W: Write,
+{
+    let next_game = AtomicU64::new(0);
+    let (sender, receiver) = mpsc::channel::<Vec<TrainingPosition>>();
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            let sender = sender.clone();
+            let (next_game, make_evaluator) = (&next_game, &make_evaluator);
+            scope.spawn(move || {
+                let mut evaluator = make_evaluator();
+                loop {
+                    let game = next_game.fetch_add(1, Ordering::Relaxed);
+                    if game >= games {
+                        break;
+                    }
+                    let positions = play_game(config, &mut evaluator, seed.wrapping_add(game));
+                    if sender.send(positions).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        // Only the workers hold senders now, so the loop below ends with them
+        drop(sender);
+
+        let mut written = 0;
+        for positions in receiver {
+            for position in &positions {
+                writeln!(output, "{}", position)?;
+            }
+            written += positions.len();
+        }
+        output.flush()?;
+        Ok(written)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{node8::BreakthroughNode, Evaluation, Player},
+        evaluation::params::EvalParams,
+        tuning::TrainingPosition,
+    };
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha12Rng;
+
+    use super::{generate, play_out, random_opening, white_score, DatagenConfig, WIN_SCORE};
+
+    #[test]
+    fn test_random_opening() {
+        let node = random_opening(6, &mut ChaCha12Rng::seed_from_u64(3));
+        assert_eq!(node.ply, 6);
+        assert_eq!(node, random_opening(6, &mut ChaCha12Rng::seed_from_u64(3)));
+        assert_eq!(random_opening(0, &mut ChaCha12Rng::seed_from_u64(3)), BreakthroughNode::default());
+    }
+
+    #[test]
+    fn test_play_out() {
+        let first_move = |node: &BreakthroughNode| node.moves().first().copied();
+        // White has no pieces left to move, so Black wins
+        let stuck = BreakthroughNode::from_fen("b7/8/8/8/8/8/8/8 w 1").unwrap();
+        assert_eq!(play_out(&stuck, first_move), Player::Black);
+        let won = BreakthroughNode::from_fen("w7/8/8/8/8/8/7b/8 b 2").unwrap();
+        assert_eq!(play_out(&won, first_move), Player::White);
+        // Any line of play ends with a winner
+        play_out(&BreakthroughNode::default(), first_move);
+    }
+
+    #[test]
+    fn test_white_score() {
+        let node = BreakthroughNode::from_fen("8/8/8/8/8/8/8/8 b 3").unwrap();
+        assert_eq!(white_score(&node, Evaluation::Heuristic(40)), -40);
+        assert_eq!(white_score(&node, Evaluation::WhiteWinPly(9)), -WIN_SCORE + 4);
+        assert_eq!(white_score(&node, Evaluation::BlackWinPly(7)), WIN_SCORE - 2);
+    }
+
+    #[test]
+    fn test_generate() {
+        let config = DatagenConfig {
+            depth: 2,
+            random_plies: 4,
+            table_size: 1024,
+        };
+        let mut output = Vec::new();
+        let written = generate(&config, 3, 2, 7, EvalParams::default, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let positions: Vec<TrainingPosition> = text
+            .lines()
+            .map(|line| TrainingPosition::parse(line).unwrap())
+            .collect();
+        assert_eq!(positions.len(), written);
+        assert!(written > 0);
+        assert!(positions.iter().all(|p| p.score.is_some()));
+
+        // The same seeds produce the same games regardless of the thread count
+        let mut single = Vec::new();
+        generate(&config, 3, 1, 7, EvalParams::default, &mut single).unwrap();
+        let mut expected: Vec<&str> = text.lines().collect();
+        let single = String::from_utf8(single).unwrap();
+        let mut actual: Vec<&str> = single.lines().collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(expected, actual);
+    }
+}
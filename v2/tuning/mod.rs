@@ -0,0 +1,234 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+};
+
+use crate::{
+    core::{
+        error::{position_in, ParseError, ParseErrorKind},
+        node8::BreakthroughNode,
+        Evaluation, Player,
+    },
+    evaluation::{fast_heuristic, params::EvalParams},
+};
+
+pub mod datagen;
+
+/**
+ * Texel-style tuning of the evaluation parameters from game outcomes
+ */
+
+// A position together with the eventual winner of the game it was taken from,
+// and optionally the search score (from White's perspective) it was given
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrainingPosition {
+    pub node: BreakthroughNode,
+    pub score: Option<i64>,
+    pub winner: Player,
+}
+
+impl TrainingPosition {
+    // Lines are `<fen> [score] <winner>`, e.g. `8/8/8/8/8/8/8/wwwwwwww w 1 350 w`
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let error = |kind, input: &str| ParseError::new(kind, input, position_in(line, input));
+        let fields: Vec<&str> = line.split_ascii_whitespace().collect();
+        let (score, winner) = match fields.len() {
+            4 => (None, fields[3]),
+            5 => (
+                Some(fields[3].parse::<i64>().map_err(|_| error(ParseErrorKind::InvalidNumber, fields[3]))?),
+                fields[4],
+            ),
+            _ => return Err(ParseError::new(ParseErrorKind::WrongFieldCount, line, 0)),
+        };
+        // The fen is the first three fields, with whatever spacing the line had
+        let fen_end = position_in(line, fields[2]) + fields[2].len();
+        let winner = match winner {
+            "w" => Player::White,
+            "b" => Player::Black,
+            _ => return Err(error(ParseErrorKind::InvalidPlayer, winner)),
+        };
+        Ok(Self {
+            node: BreakthroughNode::from_fen(&line[..fen_end])?,
+            score,
+            winner,
+        })
+    }
+}
+
+impl fmt::Display for TrainingPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.node.fen())?;
+        if let Some(score) = self.score {
+            write!(f, " {}", score)?;
+        }
+        match self.winner {
+            Player::White => write!(f, " w"),
+            Player::Black => write!(f, " b"),
+        }
+    }
+}
+
+// Read one position per line, skipping blank lines and `#` comments
+pub fn read_positions(path: &Path) -> Result<Vec<TrainingPosition>, io::Error> {
+    let file = File::open(path)?;
+    let mut positions = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        positions.push(TrainingPosition::parse(&line)?);
+    }
+    Ok(positions)
+}
+
+// Probability of a White win predicted by a heuristic score
+fn win_probability(score: i64, scale: f64) -> f64 {
+    1.0 / (1.0 + (-(score as f64) / scale).exp())
+}
+
+// Mean logistic (cross-entropy) loss between the evaluation and the game results.
+// Finished games are skipped since their result is already on the board, so they
+// carry no information about the weights.
+pub fn loss(positions: &[TrainingPosition], params: &EvalParams, scale: f64) -> f64 {
+    let mut total = 0.0;
+    let mut count = 0;
+    for position in positions {
+        if position.node.is_terminal() {
+            continue;
+        }
+        let score = match fast_heuristic(&position.node, params) {
+            Evaluation::Heuristic(n) => n,
+            _ => continue,
+        };
+        // Clamp to keep the logarithm finite on badly mispredicted positions
+        let p = win_probability(score, scale).clamp(1e-9, 1.0 - 1e-9);
+        total -= match position.winner {
+            Player::White => p.ln(),
+            Player::Black => (1.0 - p).ln(),
+        };
+        count += 1;
+    }
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
+// Find the sigmoid scale that best fits the current parameters.
+// Tuning weights without fixing this first would mostly rescale all of them.
+pub fn fit_scale(positions: &[TrainingPosition], params: &EvalParams) -> f64 {
+    let mut scale = 1000.0;
+    let mut step = 500.0;
+    let mut best = loss(positions, params, scale);
+    while step >= 1.0 {
+        let mut improved = false;
+        for candidate in [scale - step, scale + step] {
+            if candidate <= 0.0 {
+                continue;
+            }
+            let candidate_loss = loss(positions, params, candidate);
+            if candidate_loss < best {
+                (scale, best, improved) = (candidate, candidate_loss, true);
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+    scale
+}
+
+// Coordinate-wise local search: nudge each value up or down while the loss improves,
+// halving the step size whenever a full pass makes no progress.
+pub fn tune(
+    positions: &[TrainingPosition],
+    initial: &EvalParams,
+    scale: f64,
+    max_passes: usize,
+) -> EvalParams {
+    let mut values = initial.to_values();
+    let mut best = loss(positions, initial, scale);
+    let mut step = 64;
+    let mut passes = 0;
+    while step > 0 && passes < max_passes {
+        let mut improved = false;
+        for i in 0..values.len() {
+            for delta in [step, -step] {
+                let mut candidate = values.clone();
+                candidate[i] += delta;
+                let params = EvalParams::from_values(&candidate).expect("values should match layout");
+                let candidate_loss = loss(positions, &params, scale);
+                if candidate_loss < best {
+                    (values, best, improved) = (candidate, candidate_loss, true);
+                    break;
+                }
+            }
+        }
+        if !improved {
+            step /= 2;
+        }
+        passes += 1;
+    }
+    EvalParams::from_values(&values).expect("values should match layout")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{error::ParseErrorKind, node8::BreakthroughNode, Player},
+        evaluation::params::EvalParams,
+    };
+
+    use super::{loss, tune, TrainingPosition};
+
+    #[test]
+    fn test_parse_position() {
+        let position = TrainingPosition::parse("bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1 b").unwrap();
+        assert_eq!(position.node, BreakthroughNode::default());
+        assert_eq!(position.winner, Player::Black);
+        assert_eq!(position.score, None);
+        let e = TrainingPosition::parse("bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1 x").unwrap_err();
+        assert_eq!((e.kind(), e.input(), e.position()), (ParseErrorKind::InvalidPlayer, "x", 48));
+
+        let line = "bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1 -35 w";
+        let position = TrainingPosition::parse(line).unwrap();
+        assert_eq!(position.score, Some(-35));
+        assert_eq!(position.to_string(), line);
+        assert!(TrainingPosition::parse("bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwwww w 1 x w").is_err());
+        assert_eq!(TrainingPosition::parse("w").unwrap_err().kind(), ParseErrorKind::WrongFieldCount);
+    }
+
+    #[test]
+    fn test_tuning_reduces_loss() {
+        // Whoever has more pieces wins, so material should gain weight over the rest
+        let positions: Vec<TrainingPosition> = [
+            ("bbbbbbbb/bbbbbbbb/8/8/8/8/wwwwwwww/wwwwwww1 w 1", Player::Black),
+            ("bbbbbbbb/bbbbbbb1/8/8/8/8/wwwwwwww/wwwwwwww w 1", Player::White),
+            ("bbbbbbbb/bbbbbb2/8/8/8/w7/wwwwwwww/wwwwwww1 b 1", Player::White),
+            ("bbbbbbbb/bbbbbbbb/b7/8/8/8/wwwwwww1/wwwwwww1 w 1", Player::Black),
+        ]
+        .iter()
+        .map(|(fen, winner)| TrainingPosition {
+            node: BreakthroughNode::from_fen(fen).unwrap(),
+            score: None,
+            winner: winner.clone(),
+        })
+        .collect();
+        let initial = EvalParams::default();
+        let tuned = tune(&positions, &initial, 1000.0, 50);
+        assert!(loss(&positions, &tuned, 1000.0) < loss(&positions, &initial, 1000.0));
+
+        // A finished game doesn't count, even when the eval gets it wrong
+        let mut with_finished = positions.clone();
+        with_finished.push(TrainingPosition {
+            node: BreakthroughNode::from_fen("w7/bbbbbbbb/8/8/8/8/8/8 b 9").unwrap(),
+            score: None,
+            winner: Player::White,
+        });
+        assert_eq!(loss(&with_finished, &initial, 1000.0), loss(&positions, &initial, 1000.0));
+    }
+}